@@ -5,9 +5,17 @@ use std::process::Command;
 
 use env_logger::Builder;
 
-use crate::structure::bst::BstNode;
-use crate::structure::bst::BstNodeLink;
-use crate::tool::generate_dotfile_bst;
+use binarysearchtree::structure::aa::{AaBalance, AaTree};
+use binarysearchtree::structure::avl::{AvlBalance, AvlTree};
+use binarysearchtree::structure::balance::{BalancePolicy, BalancedTree, Unbalanced};
+use binarysearchtree::structure::binary_search_tree::{BinarySearchTree, DuplicatePolicy};
+use binarysearchtree::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use binarysearchtree::structure::red_black::{RedBlackBalance, RedBlackTree};
+use binarysearchtree::structure::scapegoat::{ScapegoatBalance, ScapegoatTree};
+use binarysearchtree::structure::splay::{SplayBalance, SplayTree};
+use binarysearchtree::structure::treap::{Treap, TreapBalance};
+use binarysearchtree::structure::weight_balanced::{WeightBalance, WeightBalancedTree};
+use binarysearchtree::tool::{generate_dotfile_bst, DotStyle};
 
 
 pub fn commence() {
@@ -19,21 +27,13 @@ pub fn commence() {
     println!("      2. Create the graph from the start");
     println!("      3. Exit the program");
 
-    let rootlink: BstNodeLink;
     let mut value: i32;
 
     loop {
         value = get_user_input("");
 
         match value {
-            1 => {
-                rootlink = get_predefined_tree();
-                handle_main_menu(&rootlink);
-            },
-            2 => {
-                rootlink = create_custom_tree();
-                handle_main_menu(&rootlink);
-            },
+            1 | 2 => choose_tree_kind(value == 1),
             3 => {
                 terminate();
             },
@@ -48,6 +48,49 @@ pub fn commence() {
     }
 }
 
+fn choose_tree_kind(predefined: bool) {
+    println!("    - Instruction: Please choose the kind of tree");
+    println!("      1. {}", BalancedTree::<i32>::NAME);
+    println!("      2. {}", AvlTree::<i32>::NAME);
+    println!("      3. {}", RedBlackTree::<i32>::NAME);
+    println!("      4. {}", SplayTree::<i32>::NAME);
+    println!("      5. {}", Treap::<i32>::NAME);
+    println!("      6. {}", ScapegoatTree::<i32>::NAME);
+    println!("      7. {}", AaTree::<i32>::NAME);
+    println!("      8. {}", WeightBalancedTree::<i32>::NAME);
+
+    loop {
+        let value = get_user_input("");
+
+        match value {
+            1 => start::<Unbalanced>(predefined),
+            2 => start::<AvlBalance>(predefined),
+            3 => start::<RedBlackBalance>(predefined),
+            4 => start::<SplayBalance>(predefined),
+            5 => start::<TreapBalance>(predefined),
+            6 => start::<ScapegoatBalance>(predefined),
+            7 => start::<AaBalance>(predefined),
+            8 => start::<WeightBalance>(predefined),
+            _ => {
+                invalid_option(&value);
+
+                continue;
+            }
+        }
+
+        break;
+    }
+}
+
+fn start<P: BalancePolicy<i32, ()>>(predefined: bool)
+where
+    P::Meta: DotStyle,
+{
+    let mut tree: BalancedTree<i32, (), P> = if predefined { get_predefined_tree() } else { create_custom_tree() };
+
+    handle_main_menu(&mut tree);
+}
+
 fn customized_debug() {
     Builder::new()
         .format(|buf, record| { writeln!(buf, "    {}", record.args()) })
@@ -72,9 +115,10 @@ fn get_user_input(prompt: &str) -> i32 {
     }
 }
 
-fn get_predefined_tree() -> BstNodeLink {
-    let rootlink = BstNode::new_bst_nodelink(15);
-    let query_keys = vec![
+fn get_predefined_tree<P: BalancePolicy<i32, ()>>() -> BalancedTree<i32, (), P> {
+    let mut tree = BalancedTree::with_policy(DuplicatePolicy::Reject);
+    let query_keys = [
+        15,                         // root
         5, 18,                      // 1st gen
         3, 7, 17, 20,               // 2nd gen
         2, 4, 6, 10, 16, 19, 25,    // 3rd gen
@@ -85,25 +129,47 @@ fn get_predefined_tree() -> BstNodeLink {
     ];
 
     for key in query_keys {
-        rootlink.borrow_mut().tree_insert(&rootlink, &key);
+        let _ = tree.try_insert_with_value(key, ());
     }
 
-    rootlink
+    tree
 }
 
-fn create_custom_tree() -> BstNodeLink {
-    let rootlink = BstNode::new_bst_nodelink(get_user_input("    - Instruction: Please enter a key value of the root node"));
+fn create_custom_tree<P: BalancePolicy<i32, ()>>() -> BalancedTree<i32, (), P> {
+    println!("    - Instruction: Please choose how the tree handles a key that is inserted again");
+    println!("      1. Reject the key");
+    println!("      2. Replace the existing node");
+    println!("      3. Count the occurrences in the existing node");
+    println!("      4. Keep every occurrence as a node of its own");
+
+    let policy = loop {
+        let value = get_user_input("");
+
+        match value {
+            1 => break DuplicatePolicy::Reject,
+            2 => break DuplicatePolicy::Replace,
+            3 => break DuplicatePolicy::Count,
+            4 => break DuplicatePolicy::Multiset,
+            _ => invalid_option(&value)
+        }
+    };
+
+    let tree = BalancedTree::with_policy(policy);
 
     println!("\n============================================= Info =============================================\n");
 
-    println!("    - The tree root with value {:?} is created successfully", rootlink.clone().borrow().key.unwrap());
+    println!("    - An empty {} is created successfully, the first inserted node becomes the root", BalancedTree::<i32, (), P>::NAME);
+    println!("    - Duplicate keys are handled by the {:?} policy", policy);
 
     println!("\n================================================================================================\n");
 
-    rootlink
+    tree
 }
 
-fn handle_main_menu(rootlink: &BstNodeLink) {
+fn handle_main_menu<P: BalancePolicy<i32, ()>>(tree: &mut BalancedTree<i32, (), P>)
+where
+    P::Meta: DotStyle,
+{
     let mut value: i32;
     
     loop {
@@ -111,24 +177,40 @@ fn handle_main_menu(rootlink: &BstNodeLink) {
         println!("    - Instruction: Please choose one of the following options provided by entering its number\n");
         println!("      1. Insert a new node");
         println!("      2. Delete a node");
-        println!("      3. Find the successor of a node");
-        println!("      4. Find the root node of the tree");
-        println!("      5. Find the minimum node of the tree");
-        println!("      6. Find the maximum node of the tree");
-        println!("      7. Save the current graph");
-        println!("      8. Exit the program");
+        println!("      3. Search a node");
+        println!("      4. Find the successor of a node");
+        println!("      5. Find the predecessor of a node");
+        println!("      6. Find the root node of the tree");
+        println!("      7. Find the minimum node of the tree");
+        println!("      8. Find the maximum node of the tree");
+        println!("      9. Select the node of a rank");
+        println!("      10. Find the rank of a node");
+        println!("      11. Find the median node of the tree");
+        println!("      12. Print a traversal of the tree");
+        println!("      13. Rotate left at a node");
+        println!("      14. Rotate right at a node");
+        println!("      15. Save the current graph");
+        println!("      16. Exit the program");
 
         value = get_user_input("");
 
         match value {
-            1 => tree_insert(rootlink),
-            2 => tree_delete(rootlink),
-            3 => find_successor(rootlink),
-            4 => find_root(rootlink),
-            5 => find_minimum(rootlink),
-            6 => find_maximum(rootlink),
-            7 => save_graph(rootlink),
-            8 => {
+            1 => tree_insert(tree),
+            2 => tree_delete(tree),
+            3 => search_node(tree),
+            4 => find_successor(tree),
+            5 => find_predecessor(tree),
+            6 => find_root(tree),
+            7 => find_minimum(tree),
+            8 => find_maximum(tree),
+            9 => select_node(tree),
+            10 => find_rank(tree),
+            11 => find_median(tree),
+            12 => print_traversal(tree),
+            13 => rotate_node(tree, true),
+            14 => rotate_node(tree, false),
+            15 => save_graph(tree),
+            16 => {
                 terminate();
 
                 break;
@@ -146,49 +228,77 @@ fn invalid_option(value: &i32) {
     println!("    - Error: Invalid input, there's no option number {}", value);
 }
 
-fn tree_insert(rootlink: &BstNodeLink) {
+fn tree_insert<P: BalancePolicy<i32, ()>>(tree: &mut BalancedTree<i32, (), P>) {
     println!("\n============================================ Insert ============================================\n");
 
     let value = get_user_input("    - Instruction: Please enter a key value of the new node");
 
     println!("\n============================================= Info =============================================\n");
 
-    let result = rootlink.clone().borrow().tree_search(&value);
-
-    if let Some(exist) = result {
+    if let Err(error) = tree.try_insert_with_value(value, ()) {
         println!("    - Unable to insert the key value of {}", value);
-        println!("    - The node {:?} already existed", exist.clone().borrow().key);
-    } else {
-        rootlink
-            .borrow_mut()
-            .tree_insert(&rootlink, &value);
+        println!("    - Error: {}", error);
     }
 
+    print_stats(tree);
+
     println!("\n================================================================================================\n");
 }
 
-fn tree_delete(rootlink: &BstNodeLink) {
+fn tree_delete<P: BalancePolicy<i32, ()>>(tree: &mut BalancedTree<i32, (), P>) {
     println!("\n============================================ Delete ============================================\n");
 
     let value = get_user_input("    - Instruction: Please enter a key value of the node to delete");
 
     println!("\n============================================= Info =============================================\n");
 
-    rootlink
-        .borrow_mut()
-        .tree_delete(&value);
+    tree.delete(&value);
+
+    print_stats(tree);
 
     println!("\n================================================================================================\n");
 }
 
-fn find_successor(rootlink: &BstNodeLink) {
+fn search_node<P: BalancePolicy<i32, ()>>(tree: &mut BalancedTree<i32, (), P>) {
+    println!("\n============================================ Search ============================================\n");
+
+    let value = get_user_input("    - Instruction: Please enter a key value of the node to search");
+
+    println!("\n============================================= Info =============================================\n");
+
+    match tree.access(&value) {
+        Some(node) => println!("    - The node {:?} is found", node.borrow().key),
+        None => println!("    - Node with key of {} does not exist", value)
+    }
+
+    //a self-adjusting tree moves the node it reached, so the root is shown after every lookup
+    match tree.root() {
+        Some(root) => println!("    - The root node of the tree is {:?}", root.borrow().key),
+        None => println!("    - The tree is empty, there's no root node")
+    }
+
+    print_stats(tree);
+
+    println!("\n================================================================================================\n");
+}
+
+fn print_stats<P: BalancePolicy<i32, ()>>(tree: &BalancedTree<i32, (), P>) {
+    //the same line for every kind of tree, so they can be compared side by side
+    println!("    - The height of the tree is {} after {} rotations so far", tree.height(), tree.rotations());
+
+    if let Some(stats) = tree.stats() {
+        println!("    - {}", stats);
+    }
+}
+
+fn find_successor<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
     println!("\n====================================== Find the successor ======================================\n");
 
     let value = get_user_input("    - Instruction: Please enter a key value of the node in order to find its successor");
 
     println!("\n============================================= Info =============================================\n");
 
-    let result = rootlink.clone().borrow().tree_search(&value);
+    let result = tree.search(&value);
 
     if let Some(exist) = result {
         BstNode::tree_successor(&exist);
@@ -199,31 +309,213 @@ fn find_successor(rootlink: &BstNodeLink) {
     println!("\n================================================================================================\n");
 }
 
-fn find_root(rootlink: &BstNodeLink) {
+fn find_predecessor<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
+    println!("\n===================================== Find the predecessor =====================================\n");
+
+    let value = get_user_input("    - Instruction: Please enter a key value of the node in order to find its predecessor");
+
+    println!("\n============================================= Info =============================================\n");
+
+    let result = tree.search(&value);
+
+    if let Some(exist) = result {
+        BstNode::tree_predecessor(&exist);
+    } else {
+        println!("    - Node with key of {} does not exist, failed to get predecessor", value);
+    }
+
+    println!("\n================================================================================================\n");
+}
+
+fn find_root<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
     println!("\n============================================= Info =============================================\n");
 
-    println!("    - The root node of the tree is {:?}", BstNode::get_root(&rootlink).borrow().key);
+    match tree.root() {
+        Some(root) => println!("    - The root node of the tree is {:?}", BstNode::get_root(&root).borrow().key),
+        None => println!("    - The tree is empty, there's no root node")
+    }
 
     println!("\n================================================================================================\n");   
 }
 
-fn find_minimum(rootlink: &BstNodeLink) {
+fn find_minimum<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
     println!("\n============================================= Info =============================================\n");
 
-    println!("    - The minimum node of the tree is {:?}", rootlink.borrow().minimum().borrow().key);
+    match tree.minimum() {
+        Some(minimum) => println!("    - The minimum node of the tree is {:?}", minimum.borrow().key),
+        None => println!("    - The tree is empty, there's no minimum node")
+    }
 
     println!("\n================================================================================================\n");
 }
 
-fn find_maximum(rootlink: &BstNodeLink) {
+fn find_maximum<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
     println!("\n============================================= Info =============================================\n");
 
-    println!("    - The maximum node of the tree is {:?}", rootlink.borrow().maximum().borrow().key);
+    match tree.maximum() {
+        Some(maximum) => println!("    - The maximum node of the tree is {:?}", maximum.borrow().key),
+        None => println!("    - The tree is empty, there's no maximum node")
+    }
 
     println!("\n================================================================================================\n");
 }
 
-fn save_graph(rootlink: &BstNodeLink) {
+fn select_node<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
+    println!("\n======================================== Select a node =========================================\n");
+
+    let value = get_user_input("    - Instruction: Please enter a rank, the minimum node has the rank of 1");
+
+    println!("\n============================================= Info =============================================\n");
+
+    match usize::try_from(value).ok().and_then(|rank| tree.select(rank)) {
+        Some(node) => println!("    - The node of rank {} is {:?}", value, node.borrow().key),
+        None => println!("    - There's no node of rank {}, the tree has {} nodes", value, tree.len())
+    }
+
+    println!("\n================================================================================================\n");
+}
+
+fn find_rank<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
+    println!("\n======================================== Find the rank =========================================\n");
+
+    let value = get_user_input("    - Instruction: Please enter a key value of the node in order to find its rank");
+
+    println!("\n============================================= Info =============================================\n");
+
+    match tree.rank(&value) {
+        Some(rank) => println!("    - The rank of the node {} is {} out of {} nodes", value, rank, tree.len()),
+        None => println!("    - Node with key of {} does not exist, failed to get rank", value)
+    }
+
+    println!("\n================================================================================================\n");
+}
+
+fn find_median<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
+    println!("\n============================================= Info =============================================\n");
+
+    match tree.median() {
+        Some(median) => println!("    - The median node of the tree is {:?}", median.borrow().key),
+        None => println!("    - The tree is empty, there's no median node")
+    }
+
+    println!("\n================================================================================================\n");
+}
+
+fn print_traversal<M: NodeMeta<i32, ()>>(tree: &BinarySearchTree<i32, (), M>) {
+    println!("\n====================================== Print a traversal =======================================\n");
+    println!("    - Instruction: Please choose one of the following traversal orders");
+    println!("      1. In-order");
+    println!("      2. Reverse in-order");
+    println!("      3. Pre-order");
+    println!("      4. Post-order");
+    println!("      5. Level-order");
+
+    let value = get_user_input("");
+
+    let keys: Vec<i32> = match value {
+        1 => tree.iter().collect(),
+        2 => tree.iter_rev().collect(),
+        3 => tree.preorder().collect(),
+        4 => tree.postorder().collect(),
+        5 => tree.level_order().collect(),
+        _ => {
+            invalid_option(&value);
+
+            return;
+        }
+    };
+
+    println!("\n============================================= Info =============================================\n");
+
+    if keys.is_empty() {
+        println!("    - The tree is empty, there's nothing to traverse");
+    } else {
+        println!("    - {:?}", keys);
+    }
+
+    println!("\n================================================================================================\n");
+}
+
+fn rotate_node<P: BalancePolicy<i32, ()>>(tree: &mut BalancedTree<i32, (), P>, to_left: bool)
+where
+    P::Meta: DotStyle,
+{
+    let (side, other) = if to_left { ("left", "right") } else { ("right", "left") };
+
+    if to_left {
+        println!("\n========================================= Rotate left ==========================================\n");
+    } else {
+        println!("\n========================================= Rotate right =========================================\n");
+    }
+
+    let Some(tree) = tree.tree_mut() else {
+        println!("    - Error: Rotating by hand would break the balance of the {}, only the plain binary search tree allows it", BalancedTree::<i32, (), P>::NAME);
+
+        println!("\n================================================================================================\n");
+
+        return;
+    };
+
+    let value = get_user_input(&format!("    - Instruction: Please enter a key value of the node to rotate {} at", side));
+
+    println!("\n============================================= Info =============================================\n");
+
+    match tree.search(&value) {
+        Some(node) => match if to_left { tree.rotate_left(&node) } else { tree.rotate_right(&node) } {
+            Some(top) => {
+                println!("    - The node {:?} took the place of the node {}", top.borrow().key, value);
+
+                //show the new shape right away
+                let output_dir = Path::new("graph");
+                let _ = fs::create_dir_all(output_dir);
+
+                if let Some(rootlink) = tree.root() {
+                    write_graph(&rootlink, &output_dir.join("rotation.dot"), &output_dir.join("rotation.png"));
+                }
+            },
+            None => println!("    - The node {} does not have a {} child, failed to rotate {}", value, other, side)
+        },
+        None => println!("    - Node with key of {} does not exist, failed to rotate", value)
+    }
+
+    println!("\n================================================================================================\n");
+}
+
+fn write_graph<M: DotStyle>(rootlink: &BstNodeLink<i32, (), M>, dot_full_path: &Path, png_full_path: &Path) {
+    generate_dotfile_bst(rootlink, dot_full_path.to_str().unwrap());
+
+    println!("    - The graph has been written to {}", dot_full_path.display());
+
+    match Command::new("dot")
+        .arg("-Tpng")
+        .arg(dot_full_path)
+        .arg("-o")
+        .arg(png_full_path)
+        .output() 
+    {
+        Ok(output) => {
+            if output.status.success() {
+                println!("    - Successfully converted to PNG: {}", png_full_path.display());
+            } else {
+                println!("    - {}", String::from_utf8_lossy(&output.stderr));
+            }
+        },
+        Err(_) => {
+            println!("    - Error: Failed to execute Graphviz");
+        }
+    }
+}
+
+fn save_graph<M: NodeMeta<i32, ()> + DotStyle>(tree: &BinarySearchTree<i32, (), M>) {
+    let rootlink = match tree.root() {
+        Some(root) => root,
+        None => {
+            println!("    - Error: The tree is empty, there's no graph to save");
+
+            return;
+        }
+    };
+
     println!("    - Instruction: Please define a name for file, along with the extension, for example 'example.dot'");
 
     let output_dir = Path::new("graph");
@@ -267,33 +559,12 @@ fn save_graph(rootlink: &BstNodeLink) {
             continue;
         }
 
-        let dot_full_path = output_dir.join(&dot_path);
-        let png_full_path = output_dir.join(&dot_path.replace(".dot", ".png"));
+        let dot_full_path = output_dir.join(dot_path);
+        let png_full_path = output_dir.join(dot_path.replace(".dot", ".png"));
 
         println!("\n============================================= Info =============================================\n");
 
-        generate_dotfile_bst(&rootlink, dot_full_path.to_str().unwrap());
-
-        println!("    - The graph has been written to {}", dot_full_path.display());
-
-        match Command::new("dot")
-            .arg("-Tpng")
-            .arg(dot_full_path)
-            .arg("-o")
-            .arg(&png_full_path)
-            .output() 
-        {
-            Ok(output) => {
-                if output.status.success() {
-                    println!("    - Successfully converted to PNG: {}", png_full_path.display());
-                } else {
-                    println!("    - {}", String::from_utf8_lossy(&output.stderr));
-                }
-            },
-            Err(_) => {
-                println!("    - Error: Failed to execute Graphviz");
-            }
-        }
+        write_graph(&rootlink, &dot_full_path, &png_full_path);
 
         println!("\n================================================================================================\n");
 
@@ -1,13 +1,11 @@
-mod structure;
-mod tool;
 mod cli_interface;
 
-use crate::structure::bst::BstNode;
-use crate::structure::tree::Node;
-use crate::structure::tree::NodeLink;
-use crate::structure::bst::BstNodeLink;
-use crate::tool::generate_dotfile;
-use crate::tool::generate_dotfile_bst;
+use binarysearchtree::structure::bst::BstNode;
+use binarysearchtree::structure::tree::Node;
+use binarysearchtree::structure::tree::NodeLink;
+use binarysearchtree::structure::bst::BstNodeLink;
+use binarysearchtree::tool::generate_dotfile;
+use binarysearchtree::tool::generate_dotfile_bst;
 
 fn main() {
     //turn on to test the old code
@@ -22,13 +20,13 @@ fn main() {
 
 #[allow(dead_code)]
 fn test_binary_search_tree(){
-    let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
+    let rootlink: BstNodeLink<i32> = BstNode::new_bst_nodelink(15);
     rootlink.borrow_mut().add_left_child(&rootlink, 6);
     rootlink.borrow_mut().add_right_child(&rootlink, 18);
 
     //add right subtree
-    let right_subtree: &Option<BstNodeLink> = &rootlink.borrow().right;
-    if let Some(right_tree_extract) = right_subtree {
+    let right_subtree: Option<BstNodeLink<i32>> = rootlink.borrow().right.clone();
+    if let Some(right_tree_extract) = &right_subtree {
         right_tree_extract
             .borrow_mut()
             .add_left_child(right_tree_extract, 17);
@@ -38,8 +36,8 @@ fn test_binary_search_tree(){
     }
 
     //add left subtree
-    let left_subtree: &Option<BstNodeLink> = &rootlink.borrow().left;
-    if let Some(left_tree_extract) = left_subtree {
+    let left_subtree: Option<BstNodeLink<i32>> = rootlink.borrow().left.clone();
+    if let Some(left_tree_extract) = &left_subtree {
         left_tree_extract
             .borrow_mut()
             .add_left_child(left_tree_extract, 3);
@@ -48,18 +46,18 @@ fn test_binary_search_tree(){
             .add_right_child(left_tree_extract, 7);
 
         //add left subtree terminal
-        let left_subtree_terminal = &left_tree_extract.borrow().left;
-        if let Some(terminal_left_tree_link) = left_subtree_terminal{
+        let left_subtree_terminal = left_tree_extract.borrow().left.clone();
+        if let Some(terminal_left_tree_link) = &left_subtree_terminal{
             terminal_left_tree_link.borrow_mut().add_left_child(terminal_left_tree_link, 2);
             terminal_left_tree_link.borrow_mut().add_right_child(terminal_left_tree_link, 4);
         }
         //add 2nd level right subtree of node 7
-        let second_right_subtree = &left_tree_extract.borrow().right;
-        if let Some(second_right_subtree_link) = second_right_subtree{
+        let second_right_subtree = left_tree_extract.borrow().right.clone();
+        if let Some(second_right_subtree_link) = &second_right_subtree{
             second_right_subtree_link.borrow_mut().add_right_child(second_right_subtree_link, 13);
 
-            let third_left_subtree = &second_right_subtree_link.borrow().right;
-            if let Some(third_left_subtree_link) = third_left_subtree{
+            let third_left_subtree = second_right_subtree_link.borrow().right.clone();
+            if let Some(third_left_subtree_link) = &third_left_subtree{
                 third_left_subtree_link.borrow_mut().add_left_child(third_left_subtree_link, 9);
             }
         }
@@ -70,24 +68,37 @@ fn test_binary_search_tree(){
     generate_dotfile_bst(&rootlink, main_tree_path);
 
     //tree search test
-    let search_keys = vec![9, 21];
+    let search_keys = [9, 21];
 
     for &key in search_keys.iter() {
         print!("tree search result of node with key of {} is ", key);
 
-        if let Some(_node_result) = rootlink.borrow().tree_search(&key) {
+        if let Some(_node_result) = BstNode::tree_search(&rootlink, &key) {
             println!("found");
         } else {
             println!("not found");
         }
     }
 
+    //floor and ceiling test, the keys need not exist in the tree
+    for key in [1, 5, 10, 16, 21] {
+        let floor = BstNode::floor(&rootlink, &key).and_then(|node| node.borrow().key);
+        let ceiling = BstNode::ceiling(&rootlink, &key).and_then(|node| node.borrow().key);
+        println!("floor of {} is {:?}, ceiling of {} is {:?}", key, floor, key, ceiling);
+    }
+
+    //order statistic test, the sizes are kept by add_left_child and add_right_child too
+    for i in 1..=rootlink.borrow().size {
+        let node = BstNode::tree_select(&rootlink, i).unwrap();
+        println!("rank {} is the node {:?}, whose rank is {}", i, node.borrow().key, BstNode::tree_rank(&node));
+    }
+
     //min test
-    let min_node = rootlink.borrow().minimum();
+    let min_node = BstNode::minimum(&rootlink);
     println!("minimum node of the tree is {:?}", min_node.borrow().key);
 
     //max test
-    let max_node = rootlink.borrow().maximum();
+    let max_node = BstNode::maximum(&rootlink);
     println!("maximum node of the tree is {:?}", max_node.borrow().key);
 
     //root node get test
@@ -96,7 +107,7 @@ fn test_binary_search_tree(){
 
     //successor test
     for key in 1..=21 {
-        if let Some(node) = rootlink.borrow().tree_search(&key) {
+        if let Some(node) = BstNode::tree_search(&rootlink, &key) {
             println!("\n================ successor of node ({}) =================", key);
 
             if let Some(successor) = BstNode::tree_successor(&node) {
@@ -142,7 +153,7 @@ fn test_binary_tree() {
 
     //add new child values to the right subtree
     let right_subtree = &rootlink.borrow().right;
-    if let Some(right_tree_extract) = right_subtree {
+    if let Some(right_tree_extract) = &right_subtree {
         right_tree_extract
             .borrow_mut()
             .add_right_child(right_tree_extract, 10);
@@ -166,16 +177,14 @@ fn test_binary_tree() {
     println!("Amount of nodes in current subtree: {0}", subtree_count);
 
     //Get the sibling of the leftsubtree from parent
-    let _left_subtree_sibling = Node::get_sibling(&left_subtree.as_ref().unwrap());
+    let _left_subtree_sibling = Node::get_sibling(left_subtree.as_ref().unwrap());
     //println!("sibling of left subtree {:?}", left_subtree_sibling);
 
     //get the left subtree by value
-    let left_subtree = rootlink.borrow().get_node_by_value(3);
+    let left_subtree = Node::find_node_by_value(&rootlink, 3);
     println!("left subtree seek by value {:?}", left_subtree);
     //get the left subtree by full properties
-    let another_left_subtree = rootlink
-        .borrow()
-        .get_node_by_full_property(&left_subtree.as_ref().unwrap());
+    let another_left_subtree = Node::find_node_by_full_property(&rootlink, left_subtree.as_ref().unwrap());
     println!(
         "left subtree seek by full property {:?}",
         another_left_subtree
@@ -0,0 +1,218 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::balance::{BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use crate::tool::DotStyle;
+
+/**
+ * Level of an AA node, a new node is a leaf on the level of 1 and NIL is on the level of 0
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Level(pub usize);
+
+impl Default for Level {
+    fn default() -> Self {
+        Level(1)
+    }
+}
+
+impl<K, V> NodeMeta<K, V> for Level {}
+
+impl DotStyle for Level {
+    fn dot_label(&self) -> Option<String> {
+        Some(format!("level {}", self.0))
+    }
+}
+
+pub type AaNodeLink<K, V = ()> = BstNodeLink<K, V, Level>;
+
+/**
+ * AA policy, a red-black tree where only right children may be red, which leaves two cases to fix instead of many
+ * A left child is one level below its parent, a right child is on the same level at most once in a row
+ * Skew rotates right to get rid of a left child on the same level, split rotates left and raises the middle node
+ * when two right children in a row are on the same level
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AaBalance;
+
+/**
+ * AA tree, only insert and delete differ from the plain BST, the read API comes from BinarySearchTree through Deref
+ */
+pub type AaTree<K, V = ()> = BalancedTree<K, V, AaBalance>;
+
+impl AaBalance {
+    fn level_of<K, V>(node: &Option<AaNodeLink<K, V>>) -> usize {
+        node.as_ref().map_or(0, |node| node.borrow().meta.0)
+    }
+
+    //rotate right if the left child is on the same level as x_node, return the node now on top
+    fn skew<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Level>, x_node: &AaNodeLink<K, V>) -> AaNodeLink<K, V> {
+        let left = x_node.borrow().left.clone();
+
+        match left {
+            Some(left) if left.borrow().meta == x_node.borrow().meta => {
+                debug!("- Skew: the left child {:?} of the node {:?} is on the same level, rotate right", left.borrow().key, x_node.borrow().key);
+
+                tree.rotate(x_node, false)
+            },
+            _ => x_node.clone(),
+        }
+    }
+
+    //rotate left and raise the right child if the right grandchild is on the same level as x_node, return the node now on top
+    fn split<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Level>, x_node: &AaNodeLink<K, V>) -> AaNodeLink<K, V> {
+        let right = x_node.borrow().right.clone();
+        let right_right = right.as_ref().and_then(|right| right.borrow().right.clone());
+
+        match (right, right_right) {
+            (Some(right), Some(right_right)) if right_right.borrow().meta == x_node.borrow().meta => {
+                debug!("- Split: the nodes {:?} and {:?} are both on the level of the node {:?}, rotate left and raise {:?}", right.borrow().key, right_right.borrow().key, x_node.borrow().key, right.borrow().key);
+
+                let top = tree.rotate(x_node, true);
+                top.borrow_mut().meta.0 += 1;
+                top
+            },
+            _ => x_node.clone(),
+        }
+    }
+
+    fn rebalance_after_delete<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Level>, x_node: &AaNodeLink<K, V>) -> AaNodeLink<K, V> {
+        let should_be = AaBalance::level_of(&x_node.borrow().left).min(AaBalance::level_of(&x_node.borrow().right)) + 1;
+
+        if should_be < x_node.borrow().meta.0 {
+            debug!("- The node {:?} lost a child level, lower it to the level of {}", x_node.borrow().key, should_be);
+
+            x_node.borrow_mut().meta = Level(should_be);
+
+            let right = x_node.borrow().right.clone();
+            if let Some(right) = right.filter(|right| right.borrow().meta.0 > should_be) {
+                right.borrow_mut().meta = Level(should_be);
+            }
+        }
+
+        let top = AaBalance::skew(tree, x_node);
+
+        let right = top.borrow().right.clone();
+        if let Some(right) = right {
+            let right = AaBalance::skew(tree, &right);
+
+            let right_right = right.borrow().right.clone();
+            if let Some(right_right) = right_right {
+                AaBalance::skew(tree, &right_right);
+            }
+        }
+
+        let top = AaBalance::split(tree, &top);
+
+        let right = top.borrow().right.clone();
+        if let Some(right) = right {
+            AaBalance::split(tree, &right);
+        }
+
+        top
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for AaBalance {
+    type Meta = Level;
+
+    const NAME: &'static str = "AA tree";
+
+    //skew and split every node from the parent of the new leaf up to the root
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V, Level>, node: &AaNodeLink<K, V>) {
+        let mut x_node = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+
+        while let Some(current) = x_node {
+            let top = AaBalance::skew(tree, &current);
+            let top = AaBalance::split(tree, &top);
+            x_node = BstNode::upgrade_weak_to_strong(top.borrow().parent.clone());
+        }
+    }
+
+    /**
+     * Delete as the plain BST does, a successor moving into the place of the node takes over its level
+     * Then from the lowest node that lost a node up to the root, lower the levels that are too high,
+     * skew three times and split twice
+     */
+    fn delete_node(&mut self, tree: &mut BinarySearchTree<K, V, Level>, node: &AaNodeLink<K, V>) {
+        let successor = match (&node.borrow().left, &node.borrow().right) {
+            (Some(_), Some(right)) => Some(BstNode::minimum(right)),
+            _ => None,
+        };
+
+        let (_, mut x_node) = tree.delete_node(node);
+
+        if let Some(successor) = successor {
+            successor.borrow_mut().meta = node.borrow().meta;
+        }
+
+        while let Some(current) = x_node {
+            let top = AaBalance::rebalance_after_delete(tree, &current);
+            x_node = BstNode::upgrade_weak_to_strong(top.borrow().parent.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::structure::bst::checks;
+
+    //check the levels below x_node
+    fn check_levels(x_node: &Option<AaNodeLink<u32>>) {
+        let Some(x_node) = x_node else {
+            return;
+        };
+
+        let node = x_node.borrow();
+        let level = node.meta.0;
+
+        assert_eq!(AaBalance::level_of(&node.left) + 1, level, "the left child of the node {:?} is not one level below it", node.key);
+
+        let right_level = AaBalance::level_of(&node.right);
+        assert!(right_level == level || right_level + 1 == level, "the right child of the node {:?} is on the level of {} against {}", node.key, right_level, level);
+
+        let right_right = node.right.as_ref().and_then(|right| right.borrow().right.clone());
+        assert!(AaBalance::level_of(&right_right) < level, "two right children in a row below the node {:?} are on its level", node.key);
+
+        if level > 1 {
+            assert!(node.left.is_some() && node.right.is_some(), "the node {:?} above the leaves lacks a child", node.key);
+        }
+
+        check_levels(&node.left);
+        check_levels(&node.right);
+    }
+
+    fn check(tree: &AaTree<u32>) {
+        check_levels(&tree.root());
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_levels() {
+        checks::random_inserts_and_deletes(&mut AaTree::new(), 21, check);
+    }
+
+    #[test]
+    fn sorted_input_stays_logarithmic() {
+        let mut tree = AaTree::new();
+        let keys: BTreeSet<u32> = (0..1023).collect();
+
+        for &key in &keys {
+            tree.insert(key);
+        }
+
+        checks::check_links(&tree, &keys);
+        check(&tree);
+
+        //every left child is one level down, so a path gets to twice the level of the root at most
+        let root_level = tree.root().map_or(0, |root| root.borrow().meta.0);
+        assert!(tree.height() <= 2 * root_level);
+        assert!(root_level <= 10);
+        assert!(tree.rotations() > 0);
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt::Debug;
+use std::ops::{Add, Bound, RangeBounds};
+
+use log::debug;
+
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicatePolicy};
+use crate::structure::bst::{BstNodeLink, NodeMeta};
+use crate::structure::iter::Iter;
+use crate::tool::DotStyle;
+
+/**
+ * A summary of the nodes of a subtree that is put together from the summaries of its parts
+ * combine must be associative and the default must be its identity, the summary of the nodes on the left
+ * always comes first so the order of the keys is kept for a combine that is not commutative
+ * Under the Count policy a node is summed up once, whatever its count is
+ */
+pub trait Monoid<K, V>: Clone + Default {
+    //the summary of a single node
+    fn of(key: &K, value: &V) -> Self;
+
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/**
+ * Sum of the values, the prefix sums come from the ranges that start unbounded
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sum<T>(pub T);
+
+impl<K, T: Clone + Default + Add<Output = T>> Monoid<K, T> for Sum<T> {
+    fn of(_key: &K, value: &T) -> Self {
+        Sum(value.clone())
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Sum(self.0.clone() + other.0.clone())
+    }
+}
+
+/**
+ * Smallest value, None for no nodes at all
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Min<T>(pub Option<T>);
+
+impl<T> Default for Min<T> {
+    fn default() -> Self {
+        Min(None)
+    }
+}
+
+impl<K, T: Ord + Clone> Monoid<K, T> for Min<T> {
+    fn of(_key: &K, value: &T) -> Self {
+        Min(Some(value.clone()))
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Min([&self.0, &other.0].into_iter().flatten().min().cloned())
+    }
+}
+
+/**
+ * Largest value, None for no nodes at all
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Max<T>(pub Option<T>);
+
+impl<T> Default for Max<T> {
+    fn default() -> Self {
+        Max(None)
+    }
+}
+
+impl<K, T: Ord + Clone> Monoid<K, T> for Max<T> {
+    fn of(_key: &K, value: &T) -> Self {
+        Max(Some(value.clone()))
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Max([&self.0, &other.0].into_iter().flatten().max().cloned())
+    }
+}
+
+/**
+ * The meta of a node of an aggregate tree, the summary of its whole subtree
+ * It's refreshed by the node operations of the bst module wherever the sizes are, so it stays right
+ * through every insert, delete, transplant and rotation, and after a value is replaced through the tree or an entry
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Aggregate<A>(pub A);
+
+impl<K, V, A: Monoid<K, V>> NodeMeta<K, V> for Aggregate<A> {
+    fn refresh(&mut self, key: &K, value: &V, left: Option<&Self>, right: Option<&Self>) {
+        let node = A::of(key, value);
+
+        self.0 = match (left, right) {
+            (None, None) => node,
+            (Some(left), None) => left.0.combine(&node),
+            (None, Some(right)) => node.combine(&right.0),
+            (Some(left), Some(right)) => left.0.combine(&node).combine(&right.0),
+        };
+    }
+}
+
+impl<A: Debug> DotStyle for Aggregate<A> {
+    fn dot_label(&self) -> Option<String> {
+        Some(format!("{:?}", self.0))
+    }
+}
+
+/**
+ * A BST whose every node keeps the summary of its subtree, e.g. AggregateTree<i32, i64, Sum<i64>>
+ * turns it into a sorted prefix sum and AggregateTree<i32, i64, Min<i64>> into a range minimum
+ * No link to a node is handed out, a value changed through a link would leave the summaries above it stale,
+ * so the values only change through insert, delete and update, which refresh them
+ */
+#[derive(Debug)]
+pub struct AggregateTree<K, V, A> {
+    tree: BinarySearchTree<K, V, Aggregate<A>>,
+}
+
+impl<K, V, A> Default for AggregateTree<K, V, A> {
+    fn default() -> Self {
+        AggregateTree { tree: BinarySearchTree::default() }
+    }
+}
+
+impl<K: Ord + Debug, V, A: Monoid<K, V>> FromIterator<(K, V)> for AggregateTree<K, V, A> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = AggregateTree::new();
+
+        for (key, value) in iter {
+            tree.insert_with_value(key, value);
+        }
+
+        tree
+    }
+}
+
+impl<K: Ord + Debug, V, A: Monoid<K, V>> AggregateTree<K, V, A> {
+    pub fn new() -> Self {
+        AggregateTree::default()
+    }
+
+    pub fn with_policy(policy: DuplicatePolicy) -> Self {
+        AggregateTree { tree: BinarySearchTree::with_policy(policy) }
+    }
+
+    pub fn policy(&self) -> DuplicatePolicy {
+        self.tree.policy()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.contains(key)
+    }
+
+    //see BinarySearchTree::count
+    pub fn count(&self, key: &K) -> usize {
+        self.tree.count(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        Some(self.tree.search(key)?.borrow().value.clone())
+    }
+
+    /**
+     * Insert as the plain BST does under the duplicate policy, return false if the key is rejected
+     */
+    pub fn insert_with_value(&mut self, key: K, value: V) -> bool {
+        self.tree.insert_with_value(key, value)
+    }
+
+    /**
+     * Delete the key as the plain BST does, return false if there's no such key
+     */
+    pub fn delete(&mut self, key: &K) -> bool {
+        self.tree.delete(key).is_some()
+    }
+
+    /**
+     * Change the value of the key in place and refresh the summaries from its node up to the root,
+     * return what f returns or None if there's no such key
+     */
+    pub fn update<R, F: FnOnce(&mut V) -> R>(&mut self, key: &K, f: F) -> Option<R> {
+        self.tree.update(key, f)
+    }
+
+    //the keys in order
+    pub fn iter(&self) -> Iter<'_, K, V, Aggregate<A>>
+    where
+        K: Clone,
+    {
+        self.tree.iter()
+    }
+
+    /**
+     * The summary of the nodes with keys inside the range, any form of range is accepted, e.g. 3..7, ..=7 or ..
+     * Below the node where both ends of the range part ways, every node inside the range hands over the summary
+     * of its whole subtree on the far side, so it takes O(h)
+     */
+    pub fn aggregate<R: RangeBounds<K>>(&self, range: R) -> A {
+        AggregateTree::aggregate_below(self.tree.root().as_ref(), range.start_bound(), range.end_bound())
+    }
+
+    //the summary of the whole tree, kept at the root
+    pub fn aggregate_all(&self) -> A {
+        self.tree.root().map_or_else(A::default, |root| root.borrow().meta.0.clone())
+    }
+
+    fn aggregate_below(x_node: Option<&BstNodeLink<K, V, Aggregate<A>>>, start: Bound<&K>, end: Bound<&K>) -> A {
+        let Some(x_node) = x_node else {
+            return A::default();
+        };
+
+        let node = x_node.borrow();
+
+        if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+            return node.meta.0.clone();
+        }
+
+        let key = node.key.as_ref().expect("a node in the tree has a key");
+
+        let before_start = match start {
+            Bound::Included(start) => key < start,
+            Bound::Excluded(start) => key <= start,
+            Bound::Unbounded => false,
+        };
+        let after_end = match end {
+            Bound::Included(end) => key > end,
+            Bound::Excluded(end) => key >= end,
+            Bound::Unbounded => false,
+        };
+
+        if before_start {
+            debug!("- The node {:?} comes before the range, go right", node.key);
+
+            return AggregateTree::aggregate_below(node.right.as_ref(), start, end);
+        }
+
+        if after_end {
+            debug!("- The node {:?} comes after the range, go left", node.key);
+
+            return AggregateTree::aggregate_below(node.left.as_ref(), start, end);
+        }
+
+        debug!("- The node {:?} is inside the range, only the start bounds its left subtree and only the end its right one", node.key);
+
+        let left = AggregateTree::aggregate_below(node.left.as_ref(), start, Bound::Unbounded);
+        let right = AggregateTree::aggregate_below(node.right.as_ref(), Bound::Unbounded, end);
+
+        left.combine(&A::of(key, &node.value)).combine(&right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    use super::*;
+    use crate::structure::bst::checks::TestRng;
+
+    //every form of range over the keys below 100, with both ends picked at random
+    fn random_range(rng: &mut TestRng) -> (Bound<u32>, Bound<u32>) {
+        let mut bound = || {
+            let key = rng.next_u32() % 100;
+
+            match rng.next_u32() % 3 {
+                0 => Bound::Included(key),
+                1 => Bound::Excluded(key),
+                _ => Bound::Unbounded,
+            }
+        };
+
+        (bound(), bound())
+    }
+
+    #[test]
+    fn range_sums_and_minimums_match_a_model() {
+        let mut rng = TestRng::new(24);
+        let mut sums: AggregateTree<u32, i64, Sum<i64>> = AggregateTree::new();
+        let mut minimums: AggregateTree<u32, i64, Min<i64>> = AggregateTree::new();
+        let mut model = BTreeMap::new();
+
+        for _ in 0..3000 {
+            let key = rng.next_u32() % 100;
+            let value = i64::from(rng.next_u32() % 1000) - 500;
+
+            match rng.next_u32() % 4 {
+                0 => {
+                    assert_eq!(sums.delete(&key), model.remove(&key).is_some());
+                    minimums.delete(&key);
+                },
+                1 => {
+                    let updated = model.get_mut(&key).map(|old| *old = value);
+                    assert_eq!(sums.update(&key, |old| *old = value), updated);
+                    assert_eq!(minimums.update(&key, |old| *old = value), updated);
+                },
+                _ => {
+                    let inserted = !model.contains_key(&key);
+                    if inserted {
+                        model.insert(key, value);
+                    }
+
+                    assert_eq!(sums.insert_with_value(key, value), inserted);
+                    minimums.insert_with_value(key, value);
+                }
+            }
+
+            for _ in 0..5 {
+                let range = random_range(&mut rng);
+
+                //a range that ends before it starts is empty
+                let values: Vec<i64> = match (range.0, range.1) {
+                    (Bound::Included(start) | Bound::Excluded(start), Bound::Included(end) | Bound::Excluded(end)) if start > end => Vec::new(),
+                    (Bound::Excluded(start), Bound::Excluded(end)) if start == end => Vec::new(),
+                    _ => model.range(range).map(|(_, value)| *value).collect(),
+                };
+
+                assert_eq!(sums.aggregate(range), Sum(values.iter().sum()));
+                assert_eq!(minimums.aggregate(range), Min(values.iter().min().copied()));
+            }
+
+            assert_eq!(sums.aggregate_all(), Sum(model.values().sum()));
+            assert_eq!(minimums.aggregate_all(), Min(model.values().min().copied()));
+        }
+    }
+
+    #[test]
+    fn an_update_refreshes_the_summaries_above() {
+        let mut tree: AggregateTree<u32, i64, Sum<i64>> = (0..64).map(|key| (key, 1)).collect();
+
+        assert_eq!(tree.aggregate(..), Sum(64));
+        assert_eq!(tree.update(&63, |value| *value = 100), Some(()));
+        assert_eq!(tree.aggregate(..), Sum(163));
+        assert_eq!(tree.aggregate(60..), Sum(103));
+        assert_eq!(tree.aggregate(..63), Sum(63));
+        assert_eq!(tree.update(&64, |value| *value = 100), None);
+        assert_eq!(tree.get(&63), Some(100));
+    }
+}
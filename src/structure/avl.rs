@@ -0,0 +1,186 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::balance::{BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use crate::tool::DotStyle;
+
+/**
+ * Height of the subtree of an AVL node, a new node is a leaf with the height of 1
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Height(pub usize);
+
+impl Default for Height {
+    fn default() -> Self {
+        Height(1)
+    }
+}
+
+impl<K, V> NodeMeta<K, V> for Height {}
+
+impl DotStyle for Height {}
+
+pub type AvlNodeLink<K, V = ()> = BstNodeLink<K, V, Height>;
+
+/**
+ * AVL policy, the heights of both subtrees of every node differ by 1 at most
+ * It's built on the same parent-linked nodes, the meta of a node keeps the height of its subtree
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AvlBalance;
+
+/**
+ * AVL tree, only insert and delete differ from the plain BST, the read API comes from BinarySearchTree through Deref
+ */
+pub type AvlTree<K, V = ()> = BalancedTree<K, V, AvlBalance>;
+
+impl AvlBalance {
+    fn height_of<K, V>(node: &Option<AvlNodeLink<K, V>>) -> usize {
+        node.as_ref().map_or(0, |node| node.borrow().meta.0)
+    }
+
+    fn update_height<K, V>(node: &AvlNodeLink<K, V>) {
+        let height = 1 + AvlBalance::height_of(&node.borrow().left).max(AvlBalance::height_of(&node.borrow().right));
+        node.borrow_mut().meta = Height(height);
+    }
+
+    //height of the left subtree minus height of the right subtree
+    fn balance_factor<K, V>(node: &AvlNodeLink<K, V>) -> isize {
+        AvlBalance::height_of(&node.borrow().left) as isize - AvlBalance::height_of(&node.borrow().right) as isize
+    }
+
+    fn rebalance_upward<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Height>, mut x_node: Option<AvlNodeLink<K, V>>) {
+        while let Some(current) = x_node {
+            let subtree = AvlBalance::rebalance(tree, &current);
+            x_node = subtree.borrow().parent.as_ref().and_then(|parent| parent.upgrade());
+        }
+    }
+
+    //fix the height of x_node and rotate if it's out of balance, return the node now at the top of the subtree
+    fn rebalance<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Height>, x_node: &AvlNodeLink<K, V>) -> AvlNodeLink<K, V> {
+        AvlBalance::update_height(x_node);
+
+        let balance = AvlBalance::balance_factor(x_node);
+
+        if balance > 1 {
+            debug!("- The node {:?} is left heavy with the balance factor of {}", x_node.borrow().key, balance);
+
+            let left = x_node.borrow().left.clone().expect("a left heavy node has a left child");
+
+            if AvlBalance::balance_factor(&left) < 0 {
+                debug!("- Its left child {:?} is right heavy, so it's the left-right case", left.borrow().key);
+
+                let top = tree.rotate(&left, true);
+                AvlBalance::update_height(&left);
+                AvlBalance::update_height(&top);
+            }
+
+            let top = tree.rotate(x_node, false);
+            AvlBalance::update_height(x_node);
+            AvlBalance::update_height(&top);
+
+            return top;
+        }
+
+        if balance < -1 {
+            debug!("- The node {:?} is right heavy with the balance factor of {}", x_node.borrow().key, balance);
+
+            let right = x_node.borrow().right.clone().expect("a right heavy node has a right child");
+
+            if AvlBalance::balance_factor(&right) > 0 {
+                debug!("- Its right child {:?} is left heavy, so it's the right-left case", right.borrow().key);
+
+                let top = tree.rotate(&right, false);
+                AvlBalance::update_height(&right);
+                AvlBalance::update_height(&top);
+            }
+
+            let top = tree.rotate(x_node, true);
+            AvlBalance::update_height(x_node);
+            AvlBalance::update_height(&top);
+
+            return top;
+        }
+
+        x_node.clone()
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for AvlBalance {
+    type Meta = Height;
+
+    const NAME: &'static str = "AVL tree";
+
+    //walk up from the parent of the new node and rotate wherever a node got out of balance
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V, Height>, node: &AvlNodeLink<K, V>) {
+        let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+        AvlBalance::rebalance_upward(tree, parent);
+    }
+
+    //rebalance from the lowest node whose subtree lost a node up to the root
+    fn after_delete(&mut self, tree: &mut BinarySearchTree<K, V, Height>, lowest: Option<AvlNodeLink<K, V>>) {
+        AvlBalance::rebalance_upward(tree, lowest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::checks::{self, TestRng};
+
+    //check the stored height and the balance factor below x_node, return the real height
+    fn check_heights(x_node: &Option<AvlNodeLink<u32>>) -> usize {
+        let Some(x_node) = x_node else {
+            return 0;
+        };
+
+        let node = x_node.borrow();
+        let left = check_heights(&node.left);
+        let right = check_heights(&node.right);
+
+        assert_eq!(node.meta, Height(1 + left.max(right)), "the node {:?} keeps a stale height", node.key);
+        assert!(left.abs_diff(right) <= 1, "the node {:?} is out of balance, {} against {}", node.key, left, right);
+
+        1 + left.max(right)
+    }
+
+    fn check(tree: &AvlTree<u32>) {
+        assert_eq!(check_heights(&tree.root()), tree.height());
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_balance() {
+        checks::random_inserts_and_deletes(&mut AvlTree::new(), 15, check);
+    }
+
+    #[test]
+    fn an_insert_rotates_twice_at_most() {
+        let mut rng = TestRng::new(150);
+        let mut tree = AvlTree::new();
+
+        for _ in 0..2000 {
+            let rotations = tree.rotations();
+            tree.insert(rng.next_u32() % 1000);
+
+            assert!(tree.rotations() - rotations <= 2);
+        }
+    }
+
+    #[test]
+    fn sorted_input_ends_up_perfectly_balanced() {
+        let mut tree = AvlTree::new();
+
+        for key in 0..1023 {
+            tree.insert(key);
+        }
+
+        //every insert past the first one of a level rotates once
+        assert_eq!(tree.height(), 10);
+        assert_eq!(tree.rotations(), 1023 - 10);
+        checks::check_links(&tree, &(0..1023).collect());
+        check(&tree);
+    }
+}
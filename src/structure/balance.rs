@@ -0,0 +1,237 @@
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicateKeyError, DuplicatePolicy};
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use crate::structure::iter::{IntoIter, Iter};
+
+/**
+ * How a variant of the BST keeps its shape, the search, successor and the rest of the read API stay
+ * the ones of BinarySearchTree, a policy only hooks in where the shape changes or a key is reached
+ * The core insert, access and delete of the bst module call the hooks, see BstNode::tree_insert_with_policy
+ * The meta is the balancing data kept per node, the policy itself keeps whatever the variant needs
+ * for the whole tree, e.g. a random generator or counters
+ */
+pub trait BalancePolicy<K: Ord + Debug, V>: Default {
+    type Meta: NodeMeta<K, V>;
+
+    //the name shown by the CLI
+    const NAME: &'static str;
+
+    //true if the shape may be changed by hand, e.g. by rotating at a node, a policy keeping a balance forbids it
+    const MANUAL_SHAPE: bool = false;
+
+    /**
+     * Called once the plain insert added the node, a value replaced or a duplicate counted does not call it
+     */
+    fn after_insert(&mut self, _tree: &mut BinarySearchTree<K, V, Self::Meta>, _node: &BstNodeLink<K, V, Self::Meta>) {}
+
+    /**
+     * Take the node out of the tree, the relinking of BinarySearchTree::delete_node with its transplants by default
+     * A policy that has to know where the nodes went, e.g. to fix the colours, calls delete_node itself
+     * and works from the node that took the emptied place, one that joins the subtrees its own way relinks
+     * them around root_mut and lowers the node count with shrink
+     */
+    fn delete_node(&mut self, tree: &mut BinarySearchTree<K, V, Self::Meta>, node: &BstNodeLink<K, V, Self::Meta>) {
+        let (_, lowest) = tree.delete_node(node);
+        self.after_delete(tree, lowest);
+    }
+
+    /**
+     * Called by the default delete_node with the lowest node whose subtree lost a node, None if the tree is empty
+     */
+    fn after_delete(&mut self, _tree: &mut BinarySearchTree<K, V, Self::Meta>, _lowest: Option<BstNodeLink<K, V, Self::Meta>>) {}
+
+    /**
+     * Called when a search, a delete or an insert of an existing key reaches the node, if the key is missing
+     * it's called with the last node on the path
+     */
+    fn on_access(&mut self, _tree: &mut BinarySearchTree<K, V, Self::Meta>, _node: &BstNodeLink<K, V, Self::Meta>) {}
+
+    /**
+     * A line about the work the policy did so far, shown by the CLI after every change
+     */
+    fn stats(&self, _tree: &BinarySearchTree<K, V, Self::Meta>) -> Option<String> {
+        None
+    }
+}
+
+/**
+ * Returned when a policy tuned by an alpha is given one it can't keep its balance with, e.g. the scapegoat policy
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlphaError {
+    pub alpha: f64,
+    //the range the alpha must lie in, as Display shows it
+    pub expected: &'static str,
+}
+
+impl Display for AlphaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "alpha must lie {}, got {}", self.expected, self.alpha)
+    }
+}
+
+impl Error for AlphaError {}
+
+/**
+ * The plain BST as a policy, it never changes the shape on its own, so the shape may be changed by hand
+ * The meta is left to the tree, BinarySearchTree inserts and deletes through this policy whatever meta it keeps
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unbalanced<M = ()> {
+    meta: PhantomData<M>,
+}
+
+impl<K: Ord + Debug, V, M: NodeMeta<K, V>> BalancePolicy<K, V> for Unbalanced<M> {
+    type Meta = M;
+
+    const NAME: &'static str = "Binary search tree";
+
+    const MANUAL_SHAPE: bool = true;
+}
+
+/**
+ * A BST that keeps its shape by a balance policy, every variant is one of these with its own policy,
+ * the plain BST is the one with the Unbalanced policy, so the CLI and the benchmarks can swap one for another
+ * The insert and delete are the core ones of the bst module, the policy is called at its hooks along the way
+ * The read API comes from BinarySearchTree through Deref
+ */
+#[derive(Debug)]
+pub struct BalancedTree<K: Ord + Debug, V = (), P: BalancePolicy<K, V> = Unbalanced> {
+    tree: BinarySearchTree<K, V, P::Meta>,
+    balance: P,
+}
+
+impl<K: Ord + Debug, V, P: BalancePolicy<K, V>> Default for BalancedTree<K, V, P> {
+    fn default() -> Self {
+        BalancedTree { tree: BinarySearchTree::default(), balance: P::default() }
+    }
+}
+
+impl<K: Ord + Debug, V, P: BalancePolicy<K, V>> Deref for BalancedTree<K, V, P> {
+    type Target = BinarySearchTree<K, V, P::Meta>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+impl<'a, K: Ord + Debug + Clone, V, P: BalancePolicy<K, V>> IntoIterator for &'a BalancedTree<K, V, P> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, V, P::Meta>;
+
+    fn into_iter(self) -> Iter<'a, K, V, P::Meta> {
+        self.tree.iter()
+    }
+}
+
+impl<K: Ord + Debug, P: BalancePolicy<K, ()>> IntoIterator for BalancedTree<K, (), P> {
+    type Item = K;
+    type IntoIter = IntoIter<K, (), P::Meta>;
+
+    fn into_iter(mut self) -> IntoIter<K, (), P::Meta> {
+        let len = self.tree.len();
+
+        IntoIter::new(self.tree.root_mut().take(), len)
+    }
+}
+
+//the keys go in one by one through the policy, the duplicated keys are skipped, same as insert
+impl<K: Ord + Debug, P: BalancePolicy<K, ()>> FromIterator<K> for BalancedTree<K, (), P> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut tree = BalancedTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<K: Ord + Debug, P: BalancePolicy<K, ()>> Extend<K> for BalancedTree<K, (), P> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Ord + Debug, P: BalancePolicy<K, ()>> BalancedTree<K, (), P> {
+    pub fn insert(&mut self, key: K) -> bool {
+        self.insert_with_value(key, ())
+    }
+}
+
+impl<K: Ord + Debug, V, P: BalancePolicy<K, V>> BalancedTree<K, V, P> {
+    //the name shown by the CLI
+    pub const NAME: &'static str = P::NAME;
+
+    pub fn new() -> Self {
+        BalancedTree::default()
+    }
+
+    pub fn with_policy(policy: DuplicatePolicy) -> Self {
+        BalancedTree::with_balance(policy, P::default())
+    }
+
+    /**
+     * Start with a policy that is set up other than by default, e.g. seeded or tuned
+     */
+    pub fn with_balance(policy: DuplicatePolicy, balance: P) -> Self {
+        BalancedTree { tree: BinarySearchTree::with_policy(policy), balance }
+    }
+
+    pub fn balance(&self) -> &P {
+        &self.balance
+    }
+
+    pub fn insert_with_value(&mut self, key: K, value: V) -> bool {
+        self.try_insert_with_value(key, value).is_ok()
+    }
+
+    /**
+     * Insert as the plain BST does, then hand a new node to the policy, see BstNode::tree_insert_with_policy
+     */
+    pub fn try_insert_with_value(&mut self, key: K, value: V) -> Result<BstNodeLink<K, V, P::Meta>, DuplicateKeyError<K, V>> {
+        BstNode::tree_insert_with_policy(&mut self.tree, &mut self.balance, key, value)
+    }
+
+    /**
+     * Search for the key and let the policy know what was reached, a self-adjusting policy changes the shape
+     * here so it needs the tree mutably, the search of BinarySearchTree leaves the shape as it is
+     */
+    pub fn access(&mut self, key: &K) -> Option<BstNodeLink<K, V, P::Meta>> {
+        BstNode::tree_access_with_policy(&mut self.tree, &mut self.balance, key)
+    }
+
+    /**
+     * Reach the node as an access, lower its count if the Count policy says so, otherwise let the policy take it out
+     */
+    pub fn delete(&mut self, key: &K) -> Option<BstNodeLink<K, V, P::Meta>> {
+        BstNode::tree_delete_with_policy(&mut self.tree, &mut self.balance, key)
+    }
+
+    /**
+     * A line about the work the policy did so far, shown by the CLI after every change
+     */
+    pub fn stats(&self) -> Option<String> {
+        self.balance.stats(&self.tree)
+    }
+
+    /**
+     * The underlying tree for changing its shape by hand, e.g. rotating at a node
+     * Only a policy that never restores the shape hands it out, a balanced variant would lose its balance
+     */
+    pub fn tree_mut(&mut self) -> Option<&mut BinarySearchTree<K, V, P::Meta>> {
+        P::MANUAL_SHAPE.then_some(&mut self.tree)
+    }
+
+    //the tree and the policy apart, for the variants adding operations of their own
+    pub(crate) fn into_parts(self) -> (BinarySearchTree<K, V, P::Meta>, P) {
+        (self.tree, self.balance)
+    }
+
+    pub(crate) fn from_parts(tree: BinarySearchTree<K, V, P::Meta>, balance: P) -> Self {
+        BalancedTree { tree, balance }
+    }
+}
@@ -0,0 +1,654 @@
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::iter::Rev;
+use std::ops::{Bound, RangeBounds};
+
+use log::debug;
+
+use crate::structure::balance::Unbalanced;
+use crate::structure::bst::{BstNode, BstNodeLink, DeleteSlot, NodeHandle, NodeMeta};
+use crate::structure::cursor::{Cursor, CursorMut};
+use crate::structure::entry::Entry;
+use crate::structure::iter::{IntoIter, Iter, LevelOrder, Postorder, Preorder, Range};
+
+/**
+ * What an insert does with a key that is already in the tree, chosen when the tree is built
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    //refuse the key, try_insert returns a DuplicateKeyError
+    #[default]
+    Reject,
+    //keep the node along with its key, only the value is swapped
+    Replace,
+    //keep one node per key and count the occurrences in it, a delete lowers the count first
+    Count,
+    //every occurrence gets a node of its own, placed right after the existing ones in order
+    Multiset,
+}
+
+/**
+ * Returned by try_insert under the Reject policy, the key and the value are handed back
+ */
+#[derive(Debug)]
+pub struct DuplicateKeyError<K, V = ()> {
+    pub key: K,
+    pub value: V,
+}
+
+impl<K: Debug, V> Display for DuplicateKeyError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the key {:?} already exists in the tree", self.key)
+    }
+}
+
+impl<K: Debug, V: Debug> Error for DuplicateKeyError<K, V> {}
+
+/**
+ * How far apart two keys are, used by nearest
+ * The distance must not overflow for any pair of keys, e.g. i32::MIN and i32::MAX are u32::MAX apart
+ */
+pub trait Distance {
+    type Output: Ord;
+
+    fn distance(&self, other: &Self) -> Self::Output;
+}
+
+//abs_diff gives the distance as the unsigned type of the same width, so it never overflows
+macro_rules! impl_distance {
+    ($($key:ty => $output:ty),*) => {
+        $(
+            impl Distance for $key {
+                type Output = $output;
+
+                fn distance(&self, other: &Self) -> $output {
+                    self.abs_diff(*other)
+                }
+            }
+        )*
+    };
+}
+
+impl_distance!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+impl_distance!(u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => usize);
+
+/**
+ * Owning wrapper of a BST, the root is None when the tree is empty
+ * All the operations are delegated to the node operations of the bst module
+ * len counts the nodes, so under the Count policy a key inserted many times is counted once
+ */
+#[derive(Debug)]
+pub struct BinarySearchTree<K, V = (), M = ()> {
+    root: Option<BstNodeLink<K, V, M>>,
+    len: usize,
+    policy: DuplicatePolicy,
+    rotations: usize,
+}
+
+impl<K, V, M> Default for BinarySearchTree<K, V, M> {
+    fn default() -> Self {
+        BinarySearchTree { root: None, len: 0, policy: DuplicatePolicy::default(), rotations: 0 }
+    }
+}
+
+impl<'a, K: Ord + Debug + Clone, V, M: NodeMeta<K, V>> IntoIterator for &'a BinarySearchTree<K, V, M> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, V, M>;
+
+    fn into_iter(self) -> Iter<'a, K, V, M> {
+        self.iter()
+    }
+}
+
+impl<K> IntoIterator for BinarySearchTree<K> {
+    type Item = K;
+    type IntoIter = IntoIter<K>;
+
+    fn into_iter(mut self) -> IntoIter<K> {
+        IntoIter::new(self.root.take(), self.len)
+    }
+}
+
+//the duplicated keys are skipped, same as insert
+impl<K: Ord + Debug> FromIterator<K> for BinarySearchTree<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut tree = BinarySearchTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<K: Ord + Debug> Extend<K> for BinarySearchTree<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Ord + Debug> BinarySearchTree<K> {
+    /**
+     * Insert a new key, return false if the key was rejected by the duplicate policy
+     */
+    pub fn insert(&mut self, key: K) -> bool {
+        self.insert_with_value(key, ())
+    }
+
+    pub fn try_insert(&mut self, key: K) -> Result<BstNodeLink<K>, DuplicateKeyError<K>> {
+        self.try_insert_with_value(key, ())
+    }
+}
+
+impl<K: Ord + Debug, V, M: NodeMeta<K, V>> BinarySearchTree<K, V, M> {
+    pub fn new() -> Self {
+        BinarySearchTree::default()
+    }
+
+    pub fn with_policy(policy: DuplicatePolicy) -> Self {
+        BinarySearchTree { policy, ..BinarySearchTree::default() }
+    }
+
+    pub fn policy(&self) -> DuplicatePolicy {
+        self.policy
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    //number of nodes on the longest path from the root down to a leaf, 0 for the empty tree
+    pub fn height(&self) -> usize {
+        BstNode::tree_height(&self.root)
+    }
+
+    /**
+     * Return the link of the root node, None if the tree is empty
+     */
+    pub fn root(&self) -> Option<BstNodeLink<K, V, M>> {
+        self.root.clone()
+    }
+
+    /**
+     * Insert a new key along with its value, return false if the key was rejected by the duplicate policy
+     * The first key inserted into an empty tree becomes the root
+     */
+    pub fn insert_with_value(&mut self, key: K, value: V) -> bool {
+        self.try_insert_with_value(key, value).is_ok()
+    }
+
+    /**
+     * Insert a new key along with its value according to the duplicate policy, return the node holding the key
+     * It goes through the core insert with the Unbalanced policy, so the shape is left as the insert made it
+     */
+    pub fn try_insert_with_value(&mut self, key: K, value: V) -> Result<BstNodeLink<K, V, M>, DuplicateKeyError<K, V>> {
+        BstNode::tree_insert_with_policy(self, &mut Unbalanced::default(), key, value)
+    }
+
+    /**
+     * The insert itself, the one every balance policy starts from
+     * The key is located in a single descent, the existing node is reused unless the policy is Multiset
+     */
+    pub(crate) fn place(&mut self, key: K, value: V) -> Result<BstNodeLink<K, V, M>, DuplicateKeyError<K, V>> {
+        let (key, exist) = match self.entry(key) {
+            Entry::Vacant(entry) => return Ok(entry.insert(value)),
+            Entry::Occupied(entry) => entry.into_parts(),
+        };
+
+        match self.policy {
+            DuplicatePolicy::Reject => {
+                debug!("- The node {:?} already existed, failed to insert", exist.borrow().key);
+
+                Err(DuplicateKeyError { key, value })
+            },
+            DuplicatePolicy::Replace => {
+                debug!("- The node {:?} already existed, replace its value", exist.borrow().key);
+
+                exist.borrow_mut().value = value;
+                BstNode::resize_upward(Some(exist.clone()));
+
+                Ok(exist)
+            },
+            DuplicatePolicy::Count => {
+                exist.borrow_mut().count += 1;
+
+                debug!("- The node {:?} already existed, its count goes up to {}", exist.borrow().key, exist.borrow().count);
+
+                Ok(exist)
+            },
+            DuplicatePolicy::Multiset => {
+                debug!("- The node {:?} already existed, add another node right after it", exist.borrow().key);
+
+                let node = BstNode::tree_insert_after(&exist, key, value);
+                self.len += 1;
+                Ok(node)
+            }
+        }
+    }
+
+    /**
+     * Find the node of the key or the slot where it belongs in a single descent, see Entry
+     */
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, M> {
+        Entry::new(self, key)
+    }
+
+    /**
+     * Change the value of the key in place, return what f returns or None if there's no such key
+     * The meta from the node up to the root is refreshed afterwards, so a meta made from the values stays right,
+     * unlike a change made through a link from search
+     */
+    pub fn update<R, F: FnOnce(&mut V) -> R>(&mut self, key: &K, f: F) -> Option<R> {
+        let node = self.search(key)?;
+        let result = f(&mut node.borrow_mut().value);
+        BstNode::resize_upward(Some(node));
+
+        Some(result)
+    }
+
+    /**
+     * Delete the node with the key, return the detached node or None if there's no such node
+     * Deleting the last node leaves an empty tree behind
+     * Under the Count policy a key inserted more than once loses one occurrence and its node is returned still in place
+     */
+    pub fn delete(&mut self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::tree_delete_with_policy(self, &mut Unbalanced::default(), key)
+    }
+
+    //under the Count policy a key inserted more than once only loses one occurrence, the node stays in the tree
+    pub(crate) fn lower_count(&mut self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        if self.policy != DuplicatePolicy::Count {
+            return None;
+        }
+
+        let node = self.search(key).filter(|node| node.borrow().count > 1)?;
+        node.borrow_mut().count -= 1;
+
+        debug!("- The node {:?} occurs {} more times, only its count is lowered", node.borrow().key, node.borrow().count);
+
+        Some(node)
+    }
+
+    /**
+     * How many times the key is in the tree, the count of its node under the Count policy
+     * or the number of its nodes under the Multiset policy
+     */
+    pub fn count(&self, key: &K) -> usize {
+        let Some(root) = &self.root else {
+            return 0;
+        };
+
+        match self.policy {
+            DuplicatePolicy::Multiset => BstNode::count_below(root, key, true) - BstNode::count_below(root, key, false),
+            _ => self.search(key).map_or(0, |node| node.borrow().count),
+        }
+    }
+
+    /**
+     * Same as delete, except a node with two children is replaced by its predecessor
+     */
+    pub fn delete_with_predecessor(&mut self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::tree_delete_by_predecessor_with_policy(self, &mut Unbalanced::default(), key)
+    }
+
+    pub fn search(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::tree_search(self.root.as_ref()?, key)
+    }
+
+    /**
+     * A handle to the node with the key
+     * Deleting other nodes never moves keys or values between nodes, so the handle keeps its key and value
+     */
+    pub fn handle(&self, key: &K) -> Option<NodeHandle<K, V, M>> {
+        Some(NodeHandle::new(&self.search(key)?))
+    }
+
+    //true if the node behind the handle is still in this tree
+    pub fn owns(&self, handle: &NodeHandle<K, V, M>) -> bool {
+        match (handle.get(), &self.root) {
+            (Some(node), Some(root)) => BstNode::is_node_match(&BstNode::get_root(&node), root),
+            _ => false,
+        }
+    }
+
+    /**
+     * Delete the very node behind the handle, return it detached or None if it's not in this tree
+     * The whole node goes, whatever its count is, no search by key happens
+     */
+    pub fn delete_handle(&mut self, handle: &NodeHandle<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        if !self.owns(handle) {
+            debug!("- The handle {:?} does not point into this tree, failed to delete", handle);
+
+            return None;
+        }
+
+        let node = handle.get()?;
+        self.remove_node(&node);
+
+        Some(node)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    pub fn minimum(&self) -> Option<BstNodeLink<K, V, M>> {
+        Some(BstNode::minimum(self.root.as_ref()?))
+    }
+
+    pub fn maximum(&self) -> Option<BstNodeLink<K, V, M>> {
+        Some(BstNode::maximum(self.root.as_ref()?))
+    }
+
+    //largest key less than or equal to the key
+    pub fn floor(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::floor(self.root.as_ref()?, key)
+    }
+
+    //smallest key greater than or equal to the key
+    pub fn ceiling(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::ceiling(self.root.as_ref()?, key)
+    }
+
+    //largest key strictly less than the key
+    pub fn lower(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::lower(self.root.as_ref()?, key)
+    }
+
+    //smallest key strictly greater than the key
+    pub fn higher(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::higher(self.root.as_ref()?, key)
+    }
+
+    /**
+     * Find the node whose key is the closest to the key, the key itself counts if it exists
+     * On a tie between both sides the lower key wins
+     */
+    pub fn nearest(&self, key: &K) -> Option<BstNodeLink<K, V, M>>
+    where
+        K: Distance,
+    {
+        let floor = self.floor(key);
+        let ceiling = self.ceiling(key);
+
+        match (floor, ceiling) {
+            (Some(floor), Some(ceiling)) => {
+                let below = key.distance(floor.borrow().key.as_ref().unwrap());
+                let above = key.distance(ceiling.borrow().key.as_ref().unwrap());
+
+                if above < below { Some(ceiling) } else { Some(floor) }
+            },
+            (floor, ceiling) => floor.or(ceiling),
+        }
+    }
+
+    //in-order, from the minimum to the maximum
+    pub fn iter(&self) -> Iter<'_, K, V, M>
+    where
+        K: Clone,
+    {
+        Iter::new(self.root.as_ref(), self.len)
+    }
+
+    //reverse in-order, from the maximum to the minimum
+    pub fn iter_rev(&self) -> Rev<Iter<'_, K, V, M>>
+    where
+        K: Clone,
+    {
+        self.iter().rev()
+    }
+
+    pub fn preorder(&self) -> Preorder<'_, K, V, M>
+    where
+        K: Clone,
+    {
+        Preorder::new(self.root.as_ref())
+    }
+
+    pub fn postorder(&self) -> Postorder<'_, K, V, M>
+    where
+        K: Clone,
+    {
+        Postorder::new(self.root.as_ref())
+    }
+
+    pub fn level_order(&self) -> LevelOrder<'_, K, V, M>
+    where
+        K: Clone,
+    {
+        LevelOrder::new(self.root.as_ref())
+    }
+
+    /**
+     * Iterate the keys inside the range in order, any form of range is accepted, e.g. 3..7, ..=7 or ..
+     * Subtrees that are completely outside the range are skipped
+     */
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, M>
+    where
+        K: Clone,
+    {
+        Range::new(self.root.as_ref(), range.start_bound().cloned(), range.end_bound().cloned())
+    }
+
+    /**
+     * Count the keys inside the range from the subtree sizes, without visiting the keys themselves
+     */
+    pub fn count_range<R: RangeBounds<K>>(&self, range: R) -> usize {
+        let Some(root) = &self.root else {
+            return 0;
+        };
+
+        let upto = match range.end_bound() {
+            Bound::Included(end) => BstNode::count_below(root, end, true),
+            Bound::Excluded(end) => BstNode::count_below(root, end, false),
+            Bound::Unbounded => self.len,
+        };
+        let before = match range.start_bound() {
+            Bound::Included(start) => BstNode::count_below(root, start, false),
+            Bound::Excluded(start) => BstNode::count_below(root, start, true),
+            Bound::Unbounded => 0,
+        };
+
+        upto.saturating_sub(before)
+    }
+
+    /**
+     * Delete every node whose key is inside the range, return how many nodes were deleted
+     */
+    pub fn remove_range<R: RangeBounds<K>>(&mut self, range: R) -> usize
+    where
+        K: Clone,
+    {
+        let keys: Vec<K> = self.range(range).collect();
+
+        //whole nodes are deleted, whatever their count is
+        for key in &keys {
+            if let Some(node) = self.search(key) {
+                self.remove_node(&node);
+            }
+        }
+
+        keys.len()
+    }
+
+    /**
+     * Find the node with the k-th smallest key, k starts from 1 as in the book
+     */
+    pub fn select(&self, k: usize) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::tree_select(self.root.as_ref()?, k)
+    }
+
+    /**
+     * Find the rank of the key, the minimum has the rank of 1, None if there's no such node
+     * Under the Multiset policy it's the rank of the first occurrence
+     */
+    pub fn rank(&self, key: &K) -> Option<usize> {
+        let node = self.search(key)?;
+
+        match (self.policy, &self.root) {
+            (DuplicatePolicy::Multiset, Some(root)) => Some(BstNode::count_below(root, key, false) + 1),
+            _ => Some(BstNode::tree_rank(&node)),
+        }
+    }
+
+    //lower median when the number of nodes is even
+    pub fn median(&self) -> Option<BstNodeLink<K, V, M>> {
+        self.select(self.len.div_ceil(2))
+    }
+
+    /**
+     * Find the successor of the node with the key, None if there's no such node or it is the maximum
+     */
+    pub fn successor(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::tree_successor(&self.search(key)?)
+    }
+
+    /**
+     * Find the predecessor of the node with the key, None if there's no such node or it is the minimum
+     */
+    pub fn predecessor(&self, key: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::tree_predecessor(&self.search(key)?)
+    }
+
+    //start at the node with the key, the cursor points at nothing if there's no such node
+    pub fn cursor(&self, key: &K) -> Cursor<'_, K, V, M> {
+        Cursor::new(self, self.search(key))
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, K, V, M> {
+        Cursor::new(self, self.minimum())
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, K, V, M> {
+        Cursor::new(self, self.maximum())
+    }
+
+    pub fn cursor_mut(&mut self, key: &K) -> CursorMut<'_, K, V, M> {
+        let current = self.search(key);
+        CursorMut::new(self, current)
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V, M> {
+        let current = self.minimum();
+        CursorMut::new(self, current)
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V, M> {
+        let current = self.maximum();
+        CursorMut::new(self, current)
+    }
+
+    /**
+     * Rotate left at the node, its right child takes its place and the node becomes its left child
+     * Return the node now on top, or None if the node is not in this tree or has no right child to rotate up
+     * The keys stay in order, only the shape changes
+     */
+    pub fn rotate_left(&mut self, node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        self.rotate_owned(node, true)
+    }
+
+    /**
+     * The mirror of rotate_left, the left child of the node takes its place
+     */
+    pub fn rotate_right(&mut self, node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        self.rotate_owned(node, false)
+    }
+
+    /**
+     * Number of rotations done on the tree so far, by hand or by a balanced variant keeping its balance
+     */
+    pub fn rotations(&self) -> usize {
+        self.rotations
+    }
+
+    /**
+     * Detach a node that is known to be in this tree, return the node that took its place along with its parent
+     * This is the plain relinking the balance policies build on, no hook is called, see BalancePolicy::delete_node
+     */
+    pub fn delete_node(&mut self, node: &BstNodeLink<K, V, M>) -> DeleteSlot<K, V, M> {
+        let slot = BstNode::tree_delete_node(&mut self.root, node);
+        self.shrink();
+
+        slot
+    }
+
+    //same as delete_node, except a node with two children is replaced by its predecessor
+    pub fn delete_node_by_predecessor(&mut self, node: &BstNodeLink<K, V, M>) -> DeleteSlot<K, V, M> {
+        let slot = BstNode::tree_delete_node_by_predecessor(&mut self.root, node);
+        self.shrink();
+
+        slot
+    }
+
+    //the deletes by node of the plain tree, by handle, range, cursor or entry, go through the Unbalanced policy as delete does
+    pub(crate) fn remove_node(&mut self, node: &BstNodeLink<K, V, M>) {
+        BstNode::tree_delete_node_with_policy(self, &mut Unbalanced::default(), node);
+    }
+
+    /**
+     * Rotate at a node known to be in this tree, the balance policies keep their shape through here
+     * so the variants can be compared by their rotation counts
+     * Unlike rotate_left and rotate_right nothing is checked, it panics if there's no child to rotate up
+     */
+    pub fn rotate(&mut self, node: &BstNodeLink<K, V, M>, to_left: bool) -> BstNodeLink<K, V, M> {
+        self.try_rotate(node, to_left).expect("a balanced variant only rotates up a child that exists")
+    }
+
+    //a node of another tree would be relinked around the root of this one, so it's refused like by delete_handle
+    fn rotate_owned(&mut self, node: &BstNodeLink<K, V, M>, to_left: bool) -> Option<BstNodeLink<K, V, M>> {
+        if !self.owns(&NodeHandle::new(node)) {
+            debug!("- The node {:?} is not in this tree, failed to rotate", node.borrow().key);
+
+            return None;
+        }
+
+        self.try_rotate(node, to_left)
+    }
+
+    //only a rotation that happened is counted
+    fn try_rotate(&mut self, node: &BstNodeLink<K, V, M>, to_left: bool) -> Option<BstNodeLink<K, V, M>> {
+        let top = BstNode::rotate(&mut self.root, node, to_left)?;
+        self.rotations += 1;
+
+        Some(top)
+    }
+
+    //rotations done on a subtree that was cut loose from the tree for a while
+    pub fn add_rotations(&mut self, rotations: usize) {
+        self.rotations += rotations;
+    }
+
+    //the balance policies relink nodes around the root owned by the tree, the node count is theirs to keep with shrink
+    pub fn root_mut(&mut self) -> &mut Option<BstNodeLink<K, V, M>> {
+        &mut self.root
+    }
+
+    //a tree over a subtree that was cut loose or joined by the treap, the node count is the size of the subtree
+    pub(crate) fn from_root(root: Option<BstNodeLink<K, V, M>>, policy: DuplicatePolicy) -> Self {
+        let len = BstNode::size_of(&root);
+
+        BinarySearchTree { root, len, policy, rotations: 0 }
+    }
+
+    //the first node of an empty tree, used by the entry
+    pub(crate) fn set_root(&mut self, root: BstNodeLink<K, V, M>) {
+        self.root = Some(root);
+    }
+
+    //bookkeeping after a node is attached by the cursor or the entry
+    pub(crate) fn grow(&mut self) {
+        self.len += 1;
+    }
+
+    //bookkeeping after a node is detached from the tree
+    pub fn shrink(&mut self) {
+        self.len -= 1;
+
+        if self.root.is_none() {
+            debug!("- The last node is deleted, the tree is empty now");
+        }
+    }
+}
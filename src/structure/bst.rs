@@ -1,77 +1,210 @@
 use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 use log::debug;
 
-pub type BstNodeLink = Rc<RefCell<BstNode>>;
-pub type WeakBstNodeLink = Weak<RefCell<BstNode>>;
-
-//this package implement BST wrapper
+use crate::structure::balance::BalancePolicy;
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicateKeyError};
+
+pub type BstNodeLink<K, V = (), M = ()> = Rc<RefCell<BstNode<K, V, M>>>;
+pub type WeakBstNodeLink<K, V = (), M = ()> = Weak<RefCell<BstNode<K, V, M>>>;
+//the parent of an empty child slot, along with whether the slot is its left child
+pub type InsertSlot<K, V = (), M = ()> = (BstNodeLink<K, V, M>, bool);
+//the node x that moved into the place a delete emptied, along with its parent, x may be NIL so the parent is kept apart
+//the parent is also the lowest node whose subtree lost a node
+pub type DeleteSlot<K, V = (), M = ()> = (Option<BstNodeLink<K, V, M>>, Option<BstNodeLink<K, V, M>>);
+
+//this package implement BST wrapper, the key can be anything that is totally ordered
+//every node may also carry a value payload, which is () when the tree is used as a plain set
+//size counts the nodes of the subtree rooted at the node, itself included
+//count is how many times the key was inserted, only the counting duplicate policy raises it above 1
+//meta is the balancing data of the balanced variants, e.g. the height of an AVL node, () for the plain BST,
+//or data kept about the whole subtree such as the largest end of the intervals in an interval tree
 #[derive(Debug, Clone)]
-pub struct BstNode {
-    pub key: Option<i32>,
-    pub parent: Option<WeakBstNodeLink>,
-    pub left: Option<BstNodeLink>,
-    pub right: Option<BstNodeLink>,
+pub struct BstNode<K, V = (), M = ()> {
+    pub key: Option<K>,
+    pub value: V,
+    pub size: usize,
+    pub count: usize,
+    pub meta: M,
+    pub parent: Option<WeakBstNodeLink<K, V, M>>,
+    pub left: Option<BstNodeLink<K, V, M>>,
+    pub right: Option<BstNodeLink<K, V, M>>,
 }
 
-impl BstNode {
-    //private interface
-    fn new(key: i32) -> Self {
-        BstNode {
-            key: Some(key),
-            left: None,
-            right: None,
-            parent: None,
-        }
-    }
+/**
+ * The meta of a node, refresh is called whenever the size of the node is recomputed, i.e. right after it's
+ * created and whenever its children changed by an insert, a delete, a transplant or a rotation
+ * Balancing data that the variant sets by hand keeps the default refresh, which leaves it alone,
+ * data about the whole subtree recomputes itself from the key, the value and the meta of both children
+ */
+pub trait NodeMeta<K, V>: Default {
+    fn refresh(&mut self, _key: &K, _value: &V, _left: Option<&Self>, _right: Option<&Self>) {}
+}
+
+impl<K, V> NodeMeta<K, V> for () {}
+
+/**
+ * Handle to a node of a BST that callers can store and compare
+ * Two handles are equal only if they point at the very same node, whatever their keys are
+ * A node never changes its key, so a handle keeps pointing at the same key while other nodes are deleted
+ * It holds a weak link, so it keeps neither the node nor the tree alive, get returns None once the node is gone
+ */
+pub struct NodeHandle<K, V = (), M = ()> {
+    node: WeakBstNodeLink<K, V, M>,
+}
 
-    pub fn new_bst_nodelink(value: i32) -> BstNodeLink {
-        let currentnode = BstNode::new(value);
-        let currentlink = Rc::new(RefCell::new(currentnode));
-        currentlink
+impl<K, V, M> NodeHandle<K, V, M> {
+    pub fn new(node: &BstNodeLink<K, V, M>) -> Self {
+        NodeHandle { node: Rc::downgrade(node) }
     }
 
     /**
-     * Get a copy of node link
+     * The node behind the handle, None if it has been dropped
      */
-    pub fn get_bst_nodelink_copy(&self) -> BstNodeLink {
-        Rc::new(RefCell::new(self.clone()))
+    pub fn get(&self) -> Option<BstNodeLink<K, V, M>> {
+        self.node.upgrade()
     }
 
-    fn downgrade(node: &BstNodeLink) -> WeakBstNodeLink {
-        Rc::<RefCell<BstNode>>::downgrade(node)
+    //true if the handle points at this very node
+    pub fn is(&self, node: &BstNodeLink<K, V, M>) -> bool {
+        std::ptr::eq(self.node.as_ptr(), Rc::as_ptr(node))
     }
+}
 
-    //private interface
-    fn new_with_parent(parent: &BstNodeLink, value: i32) -> BstNodeLink {
-        let mut currentnode = BstNode::new(value);
-        currentnode.parent = Some(BstNode::downgrade(parent));
-        let currentlink = Rc::new(RefCell::new(currentnode));
-        currentlink
+impl<K, V, M> From<&BstNodeLink<K, V, M>> for NodeHandle<K, V, M> {
+    fn from(node: &BstNodeLink<K, V, M>) -> Self {
+        NodeHandle::new(node)
+    }
+}
+
+impl<K, V, M> Clone for NodeHandle<K, V, M> {
+    fn clone(&self) -> Self {
+        NodeHandle { node: self.node.clone() }
+    }
+}
+
+impl<K, V, M> PartialEq for NodeHandle<K, V, M> {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.node, &other.node)
+    }
+}
+
+impl<K, V, M> Eq for NodeHandle<K, V, M> {}
+
+impl<K, V, M> Hash for NodeHandle<K, V, M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.node.as_ptr().hash(state);
+    }
+}
+
+impl<K: Debug, V, M> Debug for NodeHandle<K, V, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(node) => write!(f, "NodeHandle({:?})", node.borrow().key),
+            None => write!(f, "NodeHandle(dropped)"),
+        }
+    }
+}
+
+impl<K: Ord + Debug> BstNode<K> {
+    pub fn new_bst_nodelink(value: K) -> BstNodeLink<K> {
+        BstNode::new_bst_nodelink_with_value(value, ())
     }
 
     //add new left child, set the parent to current_node_link
-    pub fn add_left_child(&mut self, current_node_link: &BstNodeLink, value: i32) {
-        let new_node = BstNode::new_with_parent(current_node_link, value);
+    pub fn add_left_child(&mut self, current_node_link: &BstNodeLink<K>, value: K) {
+        let new_node = BstNode::new_with_parent(current_node_link, value, ());
+        let removed = BstNode::size_of(&self.left);
         self.left = Some(new_node);
+        self.adjust_size(1, removed);
     }
 
     //add new right child, set the parent to current_node_link
-    pub fn add_right_child(&mut self, current_node_link: &BstNodeLink, value: i32) {
-        let new_node = BstNode::new_with_parent(current_node_link, value);
+    pub fn add_right_child(&mut self, current_node_link: &BstNodeLink<K>, value: K) {
+        let new_node = BstNode::new_with_parent(current_node_link, value, ());
+        let removed = BstNode::size_of(&self.right);
         self.right = Some(new_node);
+        self.adjust_size(1, removed);
+    }
+
+    pub fn tree_insert(rootlink: &BstNodeLink<K>, value: K) -> BstNodeLink<K> {
+        BstNode::tree_insert_with_value(rootlink, value, ())
+    }
+}
+
+impl<K: Clone, V: Clone, M: Clone> BstNode<K, V, M> {
+    /**
+     * Get a copy of node link
+     * The copy is detached from the tree, changes made through it never reach the original node
+     */
+    pub fn get_bst_nodelink_copy(&self) -> BstNodeLink<K, V, M> {
+        Rc::new(RefCell::new(self.clone()))
+    }
+}
+
+impl<K: Ord + Debug, V, M> BstNode<K, V, M> {
+    //private interface
+    fn new(key: K, value: V) -> Self
+    where
+        M: NodeMeta<K, V>,
+    {
+        let mut node = BstNode {
+            key: Some(key),
+            value,
+            size: 1,
+            count: 1,
+            meta: M::default(),
+            left: None,
+            right: None,
+            parent: None,
+        };
+
+        //a leaf, its meta only comes from its own key and value
+        node.resize();
+        node
+    }
+
+    pub fn new_bst_nodelink_with_value(key: K, value: V) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        let currentnode = BstNode::new(key, value);
+        Rc::new(RefCell::new(currentnode))
+    }
+
+    pub fn downgrade(node: &BstNodeLink<K, V, M>) -> WeakBstNodeLink<K, V, M> {
+        Rc::<RefCell<BstNode<K, V, M>>>::downgrade(node)
+    }
+
+    //private interface
+    fn new_with_parent(parent: &BstNodeLink<K, V, M>, key: K, value: V) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        let mut currentnode = BstNode::new(key, value);
+        currentnode.parent = Some(BstNode::downgrade(parent));
+        Rc::new(RefCell::new(currentnode))
     }
 
-    //search the current tree which node fit the value
-    pub fn tree_search(&self, value: &i32) -> Option<BstNodeLink> {
-        if let Some(key) = self.key {
-            if key == *value {
-                return Some(self.get_bst_nodelink_copy());
+    /**
+     * Search the tree below node which node fit the value
+     * The returned link points at the node inside the tree, so it can be changed in place
+     */
+    pub fn tree_search(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        let current = node.borrow();
+
+        if let Some(key) = &current.key {
+            if key == value {
+                return Some(node.clone());
             }
-            if *value < key && self.left.is_some() {
-                return self.left.as_ref().unwrap().borrow().tree_search(value);
-            } else if self.right.is_some() {
-                return self.right.as_ref().unwrap().borrow().tree_search(value);
+            if value < key {
+                if let Some(left) = &current.left {
+                    return BstNode::tree_search(left, value);
+                }
+            } else if let Some(right) = &current.right {
+                return BstNode::tree_search(right, value);
             }
         }
         //default if current node is NIL
@@ -81,52 +214,115 @@ impl BstNode {
     /**seek minimum by recurs
      * in BST minimum always on the left
      */
-    pub fn minimum(&self) -> BstNodeLink {
-        if self.key.is_some() {
-            if let Some(left_node) = &self.left {
-                return left_node.borrow().minimum();
+    pub fn minimum(node: &BstNodeLink<K, V, M>) -> BstNodeLink<K, V, M> {
+        if node.borrow().key.is_some() {
+            if let Some(left_node) = &node.borrow().left {
+                return BstNode::minimum(left_node);
+            }
+        }
+        node.clone()
+    }
+
+    pub fn maximum(node: &BstNodeLink<K, V, M>) -> BstNodeLink<K, V, M> {
+        if node.borrow().key.is_some() {
+            if let Some(right_node) = &node.borrow().right {
+                return BstNode::maximum(right_node);
+            }
+        }
+        node.clone()
+    }
+
+    /**
+     * Find the node with the largest key less than or equal to value
+     * Walk down the same way as tree_search, remembering the last node that fits
+     */
+    pub fn floor(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::search_below(node, value, true)
+    }
+
+    //find the node with the largest key strictly less than value
+    pub fn lower(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::search_below(node, value, false)
+    }
+
+    /**
+     * Find the node with the smallest key greater than or equal to value
+     * The mirror of floor
+     */
+    pub fn ceiling(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::search_above(node, value, true)
+    }
+
+    //find the node with the smallest key strictly greater than value
+    pub fn higher(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::search_above(node, value, false)
+    }
+
+    //a node fits if its key is below value, when it fits the better candidate can only be on its right
+    fn search_below(node: &BstNodeLink<K, V, M>, value: &K, inclusive: bool) -> Option<BstNodeLink<K, V, M>> {
+        let mut x_node = Some(node.clone());
+        let mut candidate = None;
+
+        while let Some(current) = x_node {
+            let fits = current.borrow().key.as_ref().is_some_and(|key| key < value || (inclusive && key == value));
+
+            if fits {
+                x_node = current.borrow().right.clone();
+                candidate = Some(current);
+            } else {
+                x_node = current.borrow().left.clone();
             }
         }
-        self.get_bst_nodelink_copy()
+
+        candidate
     }
 
-    pub fn maximum(&self) -> BstNodeLink {
-        if self.key.is_some() {
-            if let Some(right_node) = &self.right {
-                return right_node.borrow().maximum();
+    //a node fits if its key is above value, when it fits the better candidate can only be on its left
+    fn search_above(node: &BstNodeLink<K, V, M>, value: &K, inclusive: bool) -> Option<BstNodeLink<K, V, M>> {
+        let mut x_node = Some(node.clone());
+        let mut candidate = None;
+
+        while let Some(current) = x_node {
+            let fits = current.borrow().key.as_ref().is_some_and(|key| key > value || (inclusive && key == value));
+
+            if fits {
+                x_node = current.borrow().left.clone();
+                candidate = Some(current);
+            } else {
+                x_node = current.borrow().right.clone();
             }
         }
-        self.get_bst_nodelink_copy()
+
+        candidate
     }
 
     /**
      * Return the root of a node, return self if not exist
      */
-    pub fn get_root(node: &BstNodeLink) -> BstNodeLink {
+    pub fn get_root(node: &BstNodeLink<K, V, M>) -> BstNodeLink<K, V, M> {
         let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
         if parent.is_none() {
             return node.clone();
         }
-        return BstNode::get_root(&parent.unwrap());
+        BstNode::get_root(&parent.unwrap())
     }
 
     /**
      * Find node successor according to the book
      * Should return None, if x_node is the highest key in the tree
      */
-    pub fn tree_successor(x_node: &BstNodeLink) -> Option<BstNodeLink> {
+    pub fn tree_successor(x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
         debug!("- Find the successor of node {:?}", x_node.borrow().key);
 
         if let Some(right_node) = &x_node.borrow().right {
-            let minimum = Some(right_node.borrow().minimum());
-            let node = minimum.clone().unwrap().clone();
+            let minimum = BstNode::minimum(right_node);
 
             debug!("- The node {:?} has a right child", x_node.borrow().key);
             debug!("- Then, take the right child as a subtree");
-            debug!("- The minimum node of that subtree is {:?}", node.borrow().key);
-            debug!("- So, the successor is {:?}", node.borrow().key);
+            debug!("- The minimum node of that subtree is {:?}", minimum.borrow().key);
+            debug!("- So, the successor is {:?}", minimum.borrow().key);
 
-            return minimum;
+            Some(minimum)
         } else {
             debug!("- The node {:?} does not have a right child", x_node.borrow().key);
 
@@ -158,249 +354,722 @@ impl BstNode {
         }
     }
 
-    pub fn tree_insert(&mut self, rootlink: &BstNodeLink, value: &i32) {
-        debug!("- Insert a new node with the key value of {value}");
+    /**
+     * Find node predecessor, the mirror of tree_successor
+     * Should return None, if x_node is the lowest key in the tree
+     */
+    pub fn tree_predecessor(x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        debug!("- Find the predecessor of node {:?}", x_node.borrow().key);
 
-        let mut current_node;
-        let mut y_node: BstNodeLink;
-        let mut x_node = BstNode::get_root(&self.get_bst_nodelink_copy());
-        let new_node = BstNode::new(*value);
+        if let Some(left_node) = &x_node.borrow().left {
+            let maximum = BstNode::maximum(left_node);
 
-        debug!("- Start traverse from the root node {:?}", x_node.clone().borrow().key);
+            debug!("- The node {:?} has a left child", x_node.borrow().key);
+            debug!("- Then, take the left child as a subtree");
+            debug!("- The maximum node of that subtree is {:?}", maximum.borrow().key);
+            debug!("- So, the predecessor is {:?}", maximum.borrow().key);
 
-        while let Some(exist) = x_node.clone().borrow().key {
-            y_node = BstNode::tree_search(&self, &exist).unwrap();
-            current_node = y_node.clone();
+            Some(maximum)
+        } else {
+            debug!("- The node {:?} does not have a left child", x_node.borrow().key);
 
-            if *value < exist {
-                debug!("- {value} is less than the key value of node {:?}", y_node.borrow().key);
+            let mut x_node = x_node.clone();
+            let mut y_node = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
 
-                if let Some(left) = y_node.borrow().left.clone() {
-                    let left_node = current_node.borrow().left.clone().unwrap().borrow().key;
-                    x_node = left;
+            while let Some(y_ref) = y_node {
+                debug!("- The node {:?} has a parent node {:?}", x_node.borrow().key, y_ref.borrow().key);
 
-                    debug!("- The node {:?} has a left child with value {:?}", current_node.borrow().key, left_node);
-                    debug!("- Traverse down to the node {:?}", left_node);
-                } else {
-                    debug!("- The node {:?} does not have a left child", y_node.borrow().key);
-                    debug!("- Found the insert point for the new node");
+                if let Some(ref right_child) = y_ref.borrow().right {
+                    if BstNode::is_node_match(right_child, &x_node) {
+                        debug!("- And, the node {:?} is the right child of that parent", x_node.borrow().key);
+                        debug!("- So, the predecessor is {:?}", y_ref.borrow().key);
 
-                    break;
+                        return Some(y_ref.clone());
+                    }
                 }
+
+                debug!("- But, the node {:?} is the left child", x_node.borrow().key);
+                debug!("- Traverse upward now to the node {:?}", y_ref.borrow().key);
+
+                x_node = y_ref.clone();
+                y_node = BstNode::upgrade_weak_to_strong(y_ref.borrow().parent.clone());
+            }
+
+            debug!("- The node {:?} does not have a parent, it's the root", x_node.borrow().key);
+            debug!("- So, the predecessor is not found");
+            None
+        }
+    }
+
+    /**
+     * Insert a new node below the root of rootlink, return the link of the new node
+     * The descent walks the live nodes, so the new node is attached directly to its parent
+     */
+    pub fn tree_insert_with_value(rootlink: &BstNodeLink<K, V, M>, key: K, value: V) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        debug!("- Insert a new node with the key value of {:?}", key);
+
+        match BstNode::descend(rootlink, &key, false) {
+            Ok(exist) => exist,
+            Err((y_node, is_left)) => BstNode::tree_insert_at(&y_node, is_left, key, value),
+        }
+    }
+
+    /**
+     * Find either the node with the key, or the empty slot where the key belongs, in a single descent
+     * The slot is the parent node along with whether the key goes to its left, it can be handed to tree_insert_at
+     */
+    pub fn tree_locate(rootlink: &BstNodeLink<K, V, M>, key: &K) -> Result<BstNodeLink<K, V, M>, InsertSlot<K, V, M>> {
+        debug!("- Locate the key value of {:?}", key);
+
+        BstNode::descend(rootlink, key, true)
+    }
+
+    //walk down from the root, equal keys go right unless stop_at_equal is set
+    fn descend(rootlink: &BstNodeLink<K, V, M>, key: &K, stop_at_equal: bool) -> Result<BstNodeLink<K, V, M>, InsertSlot<K, V, M>> {
+        let mut y_node = BstNode::get_root(rootlink);
+
+        debug!("- Start traverse from the root node {:?}", y_node.borrow().key);
+
+        loop {
+            if stop_at_equal && y_node.borrow().key.as_ref() == Some(key) {
+                debug!("- Found the node {:?}", y_node.borrow().key);
+
+                return Ok(y_node);
+            }
+
+            let is_left = Some(key) < y_node.borrow().key.as_ref();
+            let side = if is_left { "left" } else { "right" };
+            let child = if is_left {
+                debug!("- {:?} is less than the key value of node {:?}", key, y_node.borrow().key);
+
+                y_node.borrow().left.clone()
             } else {
-                debug!("- {value} is greater than the key value of node {:?}", y_node.borrow().key);
+                debug!("- {:?} is greater than the key value of node {:?}", key, y_node.borrow().key);
 
-                if let Some(right) = y_node.borrow().right.clone() {
-                    let right_node = current_node.borrow().right.clone().unwrap().borrow().key;
-                    x_node = right;
+                y_node.borrow().right.clone()
+            };
 
-                    debug!("- The node {:?} has a right child with the key value {:?}", current_node.borrow().key, right_node);
-                    debug!("- Traverse down to the node {:?}", right_node);
-                } else {
-                    debug!("- The node {:?} does not have a right child", y_node.borrow().key);
+            match child {
+                Some(child) => {
+                    debug!("- The node {:?} has a {} child with the key value {:?}", y_node.borrow().key, side, child.borrow().key);
+                    debug!("- Traverse down to the node {:?}", child.borrow().key);
+
+                    y_node = child;
+                },
+                None => {
+                    debug!("- The node {:?} does not have a {} child", y_node.borrow().key, side);
                     debug!("- Found the insert point for the new node");
 
-                    break;
+                    return Err((y_node, is_left));
                 }
             }
         }
+    }
 
-        if new_node.key < x_node.borrow().key {
-            if BstNode::is_node_match(&x_node, &self.get_bst_nodelink_copy()) {
-                self.left = Some(BstNode::new_with_parent(rootlink, new_node.key.unwrap()));
-            } else {
-                x_node.borrow_mut().add_left_child(&x_node, new_node.key.unwrap());
-            }
+    /**
+     * Attach a new node as the left or right child of y_node, the slot must be empty
+     */
+    pub fn tree_insert_at(y_node: &BstNodeLink<K, V, M>, is_left: bool, key: K, value: V) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        let new_node = BstNode::new_with_parent(y_node, key, value);
+
+        if is_left {
+            y_node.borrow_mut().left = Some(new_node.clone());
 
-            debug!("- Insert the node {:?} as the left child", new_node.key);
+            debug!("- Insert the node {:?} as the left child", new_node.borrow().key);
         } else {
-            if BstNode::is_node_match(&x_node, &self.get_bst_nodelink_copy()) {
-                self.right = Some(BstNode::new_with_parent(rootlink, new_node.key.unwrap()));
-            } else {
-                x_node.borrow_mut().add_right_child(&x_node, new_node.key.unwrap());
-            }
+            y_node.borrow_mut().right = Some(new_node.clone());
 
-            debug!("- Insert the node {:?} as the right child", new_node.key);
+            debug!("- Insert the node {:?} as the right child", new_node.borrow().key);
         }
 
+        BstNode::resize_upward(Some(y_node.clone()));
+
         debug!("- Insertion is complete\n");
+
+        new_node
+    }
+
+    /**
+     * Insert a new node right after x_node in order, without searching from the root
+     * The caller must make sure the key falls between x_node and its successor
+     */
+    pub fn tree_insert_after(x_node: &BstNodeLink<K, V, M>, key: K, value: V) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::insert_beside(x_node, key, value, true)
     }
 
-    pub fn tree_delete(&mut self, value: &i32) {
-        debug!("- Try to delete a node with the key value of {}", value);
+    /**
+     * Same as tree_insert_after, except the new node goes right before x_node
+     */
+    pub fn tree_insert_before(x_node: &BstNodeLink<K, V, M>, key: K, value: V) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::insert_beside(x_node, key, value, false)
+    }
 
-        if let Some(replaced) = self.tree_search(value) {
-            if BstNode::is_node_match(&replaced, &self.get_bst_nodelink_copy()) {
-                debug!("- The node {:?} is the root of the tree", replaced.clone().borrow().key);
-                debug!("- Set a pointer from the root node");
+    //the successor slot is the right child, or the left child of the minimum of the right subtree, mirrored for before
+    fn insert_beside(x_node: &BstNodeLink<K, V, M>, key: K, value: V, after: bool) -> BstNodeLink<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        debug!("- Insert a new node with the key value of {:?} {} the node {:?}", key, if after { "after" } else { "before" }, x_node.borrow().key);
 
-                if let Some(successor) = BstNode::tree_successor(&replaced) {
-                    debug!("- Change the node {:?} with a copy of node {:?}", replaced.clone().borrow().key, successor.clone().borrow().key);
-                    
-                    self.transplant(&self.get_bst_nodelink_copy(), &replaced, Some(successor));
-                } else {
-                    *self = BstNode::new(replaced.borrow().left.clone().unwrap().borrow().key.unwrap());
+        let subtree = if after { x_node.borrow().right.clone() } else { x_node.borrow().left.clone() };
+        let (y_node, is_left) = match subtree {
+            None => (x_node.clone(), !after),
+            Some(subtree) if after => (BstNode::minimum(&subtree), true),
+            Some(subtree) => (BstNode::maximum(&subtree), false),
+        };
+        let side = if is_left { "left" } else { "right" };
 
-                    if let Some(left) = replaced.borrow().left.clone().unwrap().borrow().left.clone() {
-                        self.left = Some(left);
-                    }
+        debug!("- The node {:?} does not have a {} child, found the insert point for the new node", y_node.borrow().key, side);
 
-                    if let Some(right) = replaced.borrow().left.clone().unwrap().borrow().right.clone() {
-                        self.right = Some(right);
-                    }
-                }
-            }
+        BstNode::tree_insert_at(&y_node, is_left, key, value)
+    }
+
+    /**
+     * Delete the node with the key from the tree owned by root, return the deleted node if found
+     * It only relinks the nodes, no balance policy is called, the trees delete through tree_delete_with_policy
+     */
+    pub fn tree_delete(root: &mut Option<BstNodeLink<K, V, M>>, value: &K) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::delete_by_key(root, value, false)
+    }
+
+    /**
+     * Same as tree_delete, except a node with two children is replaced by its predecessor
+     * The trees delete by predecessor through tree_delete_by_predecessor_with_policy
+     */
+    pub fn tree_delete_by_predecessor(root: &mut Option<BstNodeLink<K, V, M>>, value: &K) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::delete_by_key(root, value, true)
+    }
+
+    fn delete_by_key(root: &mut Option<BstNodeLink<K, V, M>>, value: &K, by_predecessor: bool) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+    {
+        debug!("- Try to delete a node with the key value of {:?}", value);
+
+        let replaced = root.as_ref().and_then(|rootlink| BstNode::tree_search(rootlink, value));
 
-            else {
-                debug!("- The node {:?} is not the root of the tree, which is the node {:?}", replaced.clone().borrow().key, self.key);
-                debug!("- Set a pointer from the root node");
+        match replaced {
+            Some(replaced) => {
+                BstNode::relink_delete(root, &replaced, by_predecessor);
 
-                self.tree_pointer(&replaced);
+                Some(replaced)
+            },
+            None => {
+                debug!("- There's no node with such key value of {:?} in the tree, failed to delete", value);
+
+                None
             }
         }
+    }
 
-        else { debug!("- There's no node with such key value of {} in the tree, failed to delete", value); }
+    /**
+     * Delete z_node from the tree owned by root according to the book
+     * The nodes are relinked instead of copied, so the other nodes keep their own key and value
+     * and any link or handle to a node that is not deleted still points at the same key afterwards
+     * Return the node x that took the emptied place along with its parent, the balanced variants fix up from there
+     * This is the relinking the policies build on, it calls no hook itself
+     */
+    pub fn tree_delete_node(root: &mut Option<BstNodeLink<K, V, M>>, z_node: &BstNodeLink<K, V, M>) -> DeleteSlot<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::relink_delete(root, z_node, false)
     }
 
-    fn tree_pointer(&mut self, replaced: &BstNodeLink) {
-        if replaced.borrow().key.unwrap() < self.key.unwrap() {
-            debug!("- The node {:?} is less than the node {:?}", replaced.borrow().key, self.key);
-            debug!("- Peek the left child of the node {:?}, and find node {:?}", self.key, self.left.clone().unwrap().borrow().key);
+    /**
+     * Same as tree_delete_node, except a node with two children is replaced by its predecessor
+     */
+    pub fn tree_delete_node_by_predecessor(root: &mut Option<BstNodeLink<K, V, M>>, z_node: &BstNodeLink<K, V, M>) -> DeleteSlot<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::relink_delete(root, z_node, true)
+    }
+
+    /**
+     * TREE-INSERT under a balance policy, every tree inserts through here, the plain one with the Unbalanced policy
+     * The key goes in as the duplicate policy of the tree says, then a new node is handed to after_insert,
+     * while an existing node the insert reached counts as an access, even if the duplicate is rejected
+     */
+    pub fn tree_insert_with_policy<P>(tree: &mut BinarySearchTree<K, V, M>, balance: &mut P, key: K, value: V) -> Result<BstNodeLink<K, V, M>, DuplicateKeyError<K, V>>
+    where
+        M: NodeMeta<K, V>,
+        P: BalancePolicy<K, V, Meta = M>,
+    {
+        let len = tree.len();
+
+        match tree.place(key, value) {
+            Ok(node) => {
+                if tree.len() > len {
+                    balance.after_insert(tree, &node);
+                } else {
+                    balance.on_access(tree, &node);
+                }
 
-            if !BstNode::is_node_match(&self.left.clone().unwrap(), replaced) {
-                debug!("- Traverse downward the current pointer {:?} to the left", self.key);
+                Ok(node)
+            },
+            Err(error) => {
+                if let Some(node) = tree.search(&error.key) {
+                    balance.on_access(tree, &node);
+                }
 
-                self.left.as_ref().unwrap().borrow_mut().tree_pointer(replaced);
+                Err(error)
             }
+        }
+    }
 
-            else { 
-                debug!("- Find an entry point from the current pointer");
+    /**
+     * Search for the key under a balance policy, the node reached is handed to on_access,
+     * or the last node on the path if the key is missing
+     */
+    pub fn tree_access_with_policy<P>(tree: &mut BinarySearchTree<K, V, M>, balance: &mut P, key: &K) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+        P: BalancePolicy<K, V, Meta = M>,
+    {
+        let root = tree.root()?;
 
-                self.delete("left"); 
+        match BstNode::tree_locate(&root, key) {
+            Ok(node) => {
+                balance.on_access(tree, &node);
+
+                Some(node)
+            },
+            Err((last, _)) => {
+                debug!("- The key {:?} is not found, the last node on the path is {:?}", key, last.borrow().key);
+
+                balance.on_access(tree, &last);
+
+                None
             }
         }
+    }
 
-        else {
-            debug!("- The node {:?} is greater than the node {:?}", replaced.borrow().key, self.key);
-            debug!("- Peek the right child of the node {:?}, and find node {:?}", self.key, self.right.clone().unwrap().borrow().key);
+    /**
+     * TREE-DELETE under a balance policy, every tree deletes through here, the plain one with the Unbalanced policy
+     * The node is reached as an access, a key counted more than once only loses one occurrence,
+     * otherwise the policy takes the node out, by tree_delete_node and then after_delete unless it does it its own way
+     */
+    pub fn tree_delete_with_policy<P>(tree: &mut BinarySearchTree<K, V, M>, balance: &mut P, key: &K) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+        P: BalancePolicy<K, V, Meta = M>,
+    {
+        BstNode::delete_with_policy(tree, balance, key, false)
+    }
 
-            if !BstNode::is_node_match(&self.right.clone().unwrap(), replaced) {
-                debug!("- Traverse downward the current pointer {:?} to the right", self.key);
+    /**
+     * Same as tree_delete_with_policy, except a node with two children is replaced by its predecessor
+     * The relinking is fixed here instead of left to delete_node, so only a policy that may have its shape
+     * changed by hand takes it, any other one gets None and the tree is left as it was
+     */
+    pub fn tree_delete_by_predecessor_with_policy<P>(tree: &mut BinarySearchTree<K, V, M>, balance: &mut P, key: &K) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+        P: BalancePolicy<K, V, Meta = M>,
+    {
+        if !P::MANUAL_SHAPE {
+            debug!("- The {} keeps its own shape, failed to delete {:?} by predecessor", P::NAME, key);
 
-                self.right.as_ref().unwrap().borrow_mut().tree_pointer(replaced);   
-            }
+            return None;
+        }
 
-            else {
-                debug!("- Find an entry point from the current pointer");
+        BstNode::delete_with_policy(tree, balance, key, true)
+    }
 
-                self.delete("right");
-            }
+    /**
+     * Take the very node out under a balance policy, no search by key happens so on_access is not called
+     * The whole node goes, whatever its count is, the deletes by handle, cursor and entry go through here
+     */
+    pub fn tree_delete_node_with_policy<P>(tree: &mut BinarySearchTree<K, V, M>, balance: &mut P, z_node: &BstNodeLink<K, V, M>)
+    where
+        M: NodeMeta<K, V>,
+        P: BalancePolicy<K, V, Meta = M>,
+    {
+        balance.delete_node(tree, z_node);
+    }
+
+    fn delete_with_policy<P>(tree: &mut BinarySearchTree<K, V, M>, balance: &mut P, key: &K, by_predecessor: bool) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+        P: BalancePolicy<K, V, Meta = M>,
+    {
+        let node = BstNode::tree_access_with_policy(tree, balance, key)?;
+
+        if let Some(counted) = tree.lower_count(key) {
+            return Some(counted);
+        }
+
+        if by_predecessor {
+            let (_, lowest) = tree.delete_node_by_predecessor(&node);
+            balance.after_delete(tree, lowest);
+        } else {
+            balance.delete_node(tree, &node);
         }
+
+        Some(node)
     }
 
-    fn delete(&mut self, entry_point: &str) {
-        let replaced: Option<BstNodeLink>;
+    fn relink_delete(root: &mut Option<BstNodeLink<K, V, M>>, z_node: &BstNodeLink<K, V, M>, by_predecessor: bool) -> DeleteSlot<K, V, M>
+    where
+        M: NodeMeta<K, V>,
+    {
+        debug!("- Delete the node {:?} by relinking its neighbours", z_node.borrow().key);
 
-        if entry_point == "left" { replaced = self.left.clone(); } 
-        else { replaced = self.right.clone(); }
+        let left = z_node.borrow().left.clone();
+        let right = z_node.borrow().right.clone();
 
-        if let Some(replaced) = replaced {
-            if replaced.borrow().left.is_none() && replaced.borrow().right.is_none() {
-                debug!("- The node does not have any children");
-                debug!("- Change the node {:?} with None", replaced.borrow().key);
+        //x_parent is the lowest node whose subtree loses a node, every size from there up to the root is refreshed at the end
+        let x_node;
+        let mut x_parent = BstNode::upgrade_weak_to_strong(z_node.borrow().parent.clone());
 
-                self.transplant(&replaced, &replaced, None);
-            } else if replaced.borrow().left.is_none() {
-                debug!("- The node has a child node {:?} on the right", replaced.borrow().right.clone().unwrap().borrow().key);
-                debug!("- Change the node {:?} with a copy of node {:?}", replaced.borrow().key, replaced.borrow().right.clone().unwrap().borrow().key);
+        match (left, right) {
+            (None, right) => {
+                debug!("- The node {:?} does not have a left child", z_node.borrow().key);
+                debug!("- Replace the node with its right subtree");
 
-                self.transplant(&replaced, &replaced, replaced.borrow().right.clone());
-            } else if replaced.borrow().right.is_none() {
-                debug!("- The node has a child node {:?} on the left", replaced.borrow().left.clone().unwrap().borrow().key);
-                debug!("- Change the node {:?} with a copy of node {:?}", replaced.borrow().key, replaced.borrow().left.clone().unwrap().borrow().key);
+                x_node = right.clone();
+                BstNode::transplant(root, z_node, right);
+            },
+            (left, None) => {
+                debug!("- The node {:?} does not have a right child", z_node.borrow().key);
+                debug!("- Replace the node with its left subtree");
 
-                self.transplant(&replaced, &replaced, replaced.borrow().left.clone());
-            } else {
-                debug!("- The node has two children");
+                x_node = left.clone();
+                BstNode::transplant(root, z_node, left);
+            },
+            (Some(left), Some(right)) if by_predecessor => {
+                let y_node = BstNode::maximum(&left);
 
-                let successor = BstNode::tree_successor(&replaced);
-                let successor_parent = BstNode::upgrade_weak_to_strong(successor.clone().unwrap().borrow().parent.clone());
+                debug!("- The node {:?} has two children, its predecessor is {:?}", z_node.borrow().key, y_node.borrow().key);
 
-                if BstNode::is_node_match(&successor_parent.unwrap(), &replaced) {
-                    debug!("- The node {:?} has a direct relationship with the successor node {:?}", replaced.clone().borrow().key, successor.clone().unwrap().borrow().key);
-                    debug!("- Change the node {:?} with a copy of node {:?}", replaced.borrow().key, successor.clone().unwrap().borrow().key);
-                    debug!("- Inspect the successor node {:?} by moving the current pointer, which is the node {:?}", successor.clone().unwrap().borrow().key, self.key);
+                x_node = y_node.borrow().left.clone();
+                x_parent = Some(y_node.clone());
 
-                    self.transplant(&replaced, &replaced, successor.clone());
-                    let right_successor = successor.as_ref().unwrap().borrow().right.clone();
+                if !BstNode::is_node_match(&y_node, &left) {
+                    debug!("- The predecessor {:?} is not the left child, lift its left subtree first", y_node.borrow().key);
 
-                    if right_successor.is_some() {
-                        right_successor.as_ref().unwrap().borrow_mut().parent = Some(BstNode::downgrade(&successor.clone().unwrap()));
-                    }
-                } else {
-                    debug!("- The node {:?} does not have a direct relationship with the successor node {:?}", replaced.clone().borrow().key, successor.clone().unwrap().borrow().key);
-                    debug!("- Change the node {:?} with a copy of node {:?}", replaced.borrow().key, successor.clone().unwrap().borrow().key);
-                    debug!("- Inspect the successor node {:?} by moving the current pointer, which is the node {:?}", successor.clone().unwrap().borrow().key, self.key);
+                    x_parent = BstNode::upgrade_weak_to_strong(y_node.borrow().parent.clone());
 
-                    self.transplant(&replaced, &successor.clone().unwrap(), successor.as_ref().unwrap().borrow().right.clone());
+                    BstNode::transplant(root, &y_node, x_node.clone());
 
-                    if BstNode::is_node_match(&self.left.clone().unwrap(), &replaced) {
-                        replaced.borrow_mut().key = successor.clone().unwrap().borrow().key;
-                    }
+                    left.borrow_mut().parent = Some(BstNode::downgrade(&y_node));
+                    y_node.borrow_mut().left = Some(left);
+                }
 
-                    if BstNode::is_node_match(&self.right.clone().unwrap(), &replaced) {
-                        replaced.borrow_mut().key = successor.clone().unwrap().borrow().key;
-                    }
+                debug!("- Put the predecessor {:?} in place of the node {:?}", y_node.borrow().key, z_node.borrow().key);
+
+                BstNode::transplant(root, z_node, Some(y_node.clone()));
+
+                right.borrow_mut().parent = Some(BstNode::downgrade(&y_node));
+                y_node.borrow_mut().right = Some(right);
+            },
+            (Some(left), Some(right)) => {
+                let y_node = BstNode::minimum(&right);
+
+                debug!("- The node {:?} has two children, its successor is {:?}", z_node.borrow().key, y_node.borrow().key);
+
+                x_node = y_node.borrow().right.clone();
+                x_parent = Some(y_node.clone());
+
+                if !BstNode::is_node_match(&y_node, &right) {
+                    debug!("- The successor {:?} is not the right child, lift its right subtree first", y_node.borrow().key);
+
+                    x_parent = BstNode::upgrade_weak_to_strong(y_node.borrow().parent.clone());
+
+                    BstNode::transplant(root, &y_node, x_node.clone());
+
+                    right.borrow_mut().parent = Some(BstNode::downgrade(&y_node));
+                    y_node.borrow_mut().right = Some(right);
                 }
+
+                debug!("- Put the successor {:?} in place of the node {:?}", y_node.borrow().key, z_node.borrow().key);
+
+                BstNode::transplant(root, z_node, Some(y_node.clone()));
+
+                left.borrow_mut().parent = Some(BstNode::downgrade(&y_node));
+                y_node.borrow_mut().left = Some(left);
             }
         }
+
+        BstNode::resize_upward(x_parent.clone());
+
+        let mut deleted = z_node.borrow_mut();
+        deleted.parent = None;
+        deleted.left = None;
+        deleted.right = None;
+        deleted.size = 1;
+
+        (x_node, x_parent)
+    }
+
+    /**
+     * Rotate the subtree of x_node to the left, its right child y_node takes its place and x_node becomes
+     * the left child of y_node, the left subtree of y_node moves over to x_node, return y_node
+     * The parent links, the child slot of the parent and the root owned by the caller are all fixed
+     * Return None and leave the tree as it is if x_node has no right child
+     */
+    pub fn rotate_left(root: &mut Option<BstNodeLink<K, V, M>>, x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::rotate(root, x_node, true)
+    }
+
+    /**
+     * The mirror of rotate_left, the left child of x_node takes its place
+     */
+    pub fn rotate_right(root: &mut Option<BstNodeLink<K, V, M>>, x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+    {
+        BstNode::rotate(root, x_node, false)
     }
 
-    fn transplant(&mut self, reference: &BstNodeLink, replaced: &BstNodeLink, replacement: Option<BstNodeLink>) {
-        if let Some(ref replacement) = replacement {
-            replacement.borrow_mut().parent = replaced.borrow().parent.clone();
+    //rotate_left if to_left holds, rotate_right otherwise, for the variants that pick the side at runtime
+    pub fn rotate(root: &mut Option<BstNodeLink<K, V, M>>, x_node: &BstNodeLink<K, V, M>, to_left: bool) -> Option<BstNodeLink<K, V, M>>
+    where
+        M: NodeMeta<K, V>,
+    {
+        let (side, other) = if to_left { ("left", "right") } else { ("right", "left") };
+        let y_node = if to_left { x_node.borrow().right.clone() } else { x_node.borrow().left.clone() };
+
+        let Some(y_node) = y_node else {
+            debug!("- The node {:?} does not have a {} child, there's nothing to rotate {}", x_node.borrow().key, other, side);
+
+            return None;
+        };
+
+        debug!("- Rotate {} at the node {:?}, its {} child {:?} moves up", side, x_node.borrow().key, other, y_node.borrow().key);
+
+        //the inner subtree of y_node changes hands
+        let beta = if to_left { y_node.borrow_mut().left.take() } else { y_node.borrow_mut().right.take() };
+
+        if let Some(ref beta) = beta {
+            debug!("- The {} subtree {:?} of the node {:?} becomes the {} subtree of the node {:?}", side, beta.borrow().key, y_node.borrow().key, other, x_node.borrow().key);
+
+            beta.borrow_mut().parent = Some(BstNode::downgrade(x_node));
         }
 
-        if replaced.borrow().parent.is_none() {
-            if let Some(_) = replacement.clone() {
-                self.tree_pointer(&replacement.clone().unwrap());
-                self.key = replacement.clone().unwrap().borrow().key;   
-            }
+        if to_left {
+            x_node.borrow_mut().right = beta;
+        } else {
+            x_node.borrow_mut().left = beta;
         }
 
-        else if replaced.borrow().left.is_some() && replaced.borrow().right.is_some() {
-            replacement.as_ref().unwrap().borrow_mut().left = reference.borrow().left.clone();
-            replacement.as_ref().unwrap().borrow_mut().left.as_ref().unwrap().borrow_mut().parent = Some(BstNode::downgrade(&replacement.clone().unwrap()));
+        debug!("- Put the node {:?} in place of the node {:?}", y_node.borrow().key, x_node.borrow().key);
 
-            if let Some(_) = self.left.clone() {
-                if BstNode::is_node_match(&self.left.clone().unwrap(), reference) {
-                    self.left = replacement.clone();
-                }   
-            }
+        BstNode::transplant(root, x_node, Some(y_node.clone()));
+
+        x_node.borrow_mut().parent = Some(BstNode::downgrade(&y_node));
+
+        if to_left {
+            y_node.borrow_mut().left = Some(x_node.clone());
+        } else {
+            y_node.borrow_mut().right = Some(x_node.clone());
+        }
+
+        debug!("- The node {:?} is now the {} child of the node {:?}", x_node.borrow().key, side, y_node.borrow().key);
+
+        x_node.borrow_mut().resize();
+        y_node.borrow_mut().resize();
+
+        Some(y_node)
+    }
+
+    /**
+     * Number of nodes on the longest path from node down to a leaf, NIL has the height of 0
+     */
+    pub fn tree_height(node: &Option<BstNodeLink<K, V, M>>) -> usize {
+        match node {
+            None => 0,
+            Some(node) => 1 + BstNode::tree_height(&node.borrow().left).max(BstNode::tree_height(&node.borrow().right)),
+        }
+    }
+
+    /**
+     * Subtree size of a possibly NIL link, NIL counts as 0
+     */
+    pub fn size_of(node: &Option<BstNodeLink<K, V, M>>) -> usize {
+        node.as_ref().map_or(0, |node| node.borrow().size)
+    }
+
+    //recompute the size and refresh the meta from both children, the children must be up to date already
+    pub fn resize(&mut self)
+    where
+        M: NodeMeta<K, V>,
+    {
+        self.size = 1 + BstNode::size_of(&self.left) + BstNode::size_of(&self.right);
+
+        let left = self.left.as_ref().map(|left| left.borrow());
+        let right = self.right.as_ref().map(|right| right.borrow());
+
+        if let Some(key) = &self.key {
+            self.meta.refresh(key, &self.value, left.as_ref().map(|left| &left.meta), right.as_ref().map(|right| &right.meta));
+        }
+    }
+
+    //a subtree of removed nodes below self is replaced by added nodes, self is still borrowed by the caller
+    //so the sizes are adjusted by the difference instead of recomputed from the children
+    fn adjust_size(&mut self, added: usize, removed: usize) {
+        self.size = self.size + added - removed;
+
+        let mut x_node = BstNode::upgrade_weak_to_strong(self.parent.clone());
+        while let Some(current) = x_node {
+            let mut current = current.borrow_mut();
+            current.size = current.size + added - removed;
+            x_node = BstNode::upgrade_weak_to_strong(current.parent.clone());
+        }
+    }
+
+    //recompute the sizes of x_node and all of its ancestors, used after the tree shape below x_node changed
+    pub fn resize_upward(mut x_node: Option<BstNodeLink<K, V, M>>)
+    where
+        M: NodeMeta<K, V>,
+    {
+        while let Some(current) = x_node {
+            current.borrow_mut().resize();
+            x_node = BstNode::upgrade_weak_to_strong(current.borrow().parent.clone());
+        }
+    }
+
+    /**
+     * Find the node with the i-th smallest key below node according to the book, i starts from 1
+     * Return None if i is 0 or larger than the subtree size
+     */
+    pub fn tree_select(node: &BstNodeLink<K, V, M>, i: usize) -> Option<BstNodeLink<K, V, M>> {
+        debug!("- Select the node of rank {} below the node {:?}", i, node.borrow().key);
+
+        let mut x_node = node.clone();
+        let mut i = i;
+
+        loop {
+            let r = BstNode::size_of(&x_node.borrow().left) + 1;
+
+            debug!("- The node {:?} has the rank {} in its own subtree", x_node.borrow().key, r);
+
+            let next = if i == r {
+                debug!("- Found the node {:?}\n", x_node.borrow().key);
+
+                return Some(x_node);
+            } else if i < r {
+                debug!("- {} is less than {}, go to the left subtree", i, r);
 
-            if let Some(_) = self.right.clone() {
-                if BstNode::is_node_match(&self.right.clone().unwrap(), reference) {
-                    self.right = replacement.clone();
+                x_node.borrow().left.clone()
+            } else {
+                debug!("- {} is greater than {}, go to the right subtree looking for the rank {}", i, r, i - r);
+
+                i -= r;
+                x_node.borrow().right.clone()
+            };
+
+            match next {
+                Some(next) => x_node = next,
+                None => {
+                    debug!("- The subtree is too small, there's no node of such rank\n");
+
+                    return None;
                 }
             }
-        } 
-        
-        else {
-            if let Some(_) = self.left.clone() {
-                if BstNode::is_node_match(&self.left.clone().unwrap(), replaced) {
-                    self.left = replacement.clone();
-                }   
+        }
+    }
+
+    /**
+     * Find the rank of x_node inside the whole tree according to the book, the minimum has the rank of 1
+     */
+    pub fn tree_rank(x_node: &BstNodeLink<K, V, M>) -> usize {
+        debug!("- Find the rank of the node {:?}", x_node.borrow().key);
+
+        let mut r = BstNode::size_of(&x_node.borrow().left) + 1;
+        let mut y_node = x_node.clone();
+
+        debug!("- The node {:?} has the rank {} in its own subtree", y_node.borrow().key, r);
+
+        loop {
+            let parent = BstNode::upgrade_weak_to_strong(y_node.borrow().parent.clone());
+            let Some(parent) = parent else {
+                break;
+            };
+            let is_right = parent.borrow().right.as_ref().is_some_and(|right| BstNode::is_node_match(right, &y_node));
+
+            if is_right {
+                r += BstNode::size_of(&parent.borrow().left) + 1;
+
+                debug!("- Coming from the right of the node {:?}, the rank goes up to {}", parent.borrow().key, r);
             }
 
-            if let Some(_) = self.right.clone() {
-                if BstNode::is_node_match(&self.right.clone().unwrap(), replaced) {
-                    self.right = replacement.clone();
-                }
+            y_node = parent;
+        }
+
+        debug!("- The rank of the node {:?} is {}\n", x_node.borrow().key, r);
+
+        r
+    }
+
+    /**
+     * Count the keys below node that are less than the key, or less than or equal to it if inclusive
+     */
+    pub fn count_below(node: &BstNodeLink<K, V, M>, value: &K, inclusive: bool) -> usize {
+        let mut x_node = Some(node.clone());
+        let mut count = 0;
+
+        while let Some(current) = x_node {
+            let current = current.borrow();
+            let is_below = match &current.key {
+                Some(key) => key < value || (inclusive && key == value),
+                None => false,
+            };
+
+            if is_below {
+                count += BstNode::size_of(&current.left) + 1;
+                x_node = current.right.clone();
+            } else {
+                x_node = current.left.clone();
             }
+        }
+
+        count
+    }
+
+    /**
+     * Replace the subtree rooted at u_node with the subtree rooted at v_node according to the book
+     * If u_node is the root, the root owned by the caller is replaced instead
+     */
+    pub fn transplant(root: &mut Option<BstNodeLink<K, V, M>>, u_node: &BstNodeLink<K, V, M>, v_node: Option<BstNodeLink<K, V, M>>) {
+        let parent = BstNode::upgrade_weak_to_strong(u_node.borrow().parent.clone());
+
+        if let Some(ref v_node) = v_node {
+            v_node.borrow_mut().parent = u_node.borrow().parent.clone();
+        }
 
-            if !BstNode::is_node_match(replaced, reference) {
+        match parent {
+            None => *root = v_node,
+            Some(parent) => {
+                let is_left = parent.borrow().left.as_ref().is_some_and(|left| BstNode::is_node_match(left, u_node));
 
-                self.tree_pointer(replaced);
+                if is_left {
+                    parent.borrow_mut().left = v_node;
+                } else {
+                    parent.borrow_mut().right = v_node;
+                }
             }
         }
     }
@@ -409,17 +1078,17 @@ impl BstNode {
      * Alternate simpler version of tree_successor that made use of is_nil checking
      */
     #[allow(dead_code)]
-    pub fn tree_successor_simpler(x_node: &BstNodeLink) -> Option<BstNodeLink>{
+    pub fn tree_successor_simpler(x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>>{
         //create a shadow of x_node so it can mutate
         let mut x_node = x_node;
         let right_node = &x_node.borrow().right.clone();
-        if BstNode::is_nil(right_node)!=true{
-            return Some(right_node.clone().unwrap().borrow().minimum());
+        if !BstNode::is_nil(right_node){
+            return Some(BstNode::minimum(&right_node.clone().unwrap()));
         }
 
         let mut y_node = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
         let y_node_right = &y_node.clone().unwrap().borrow().right.clone();
-        let mut y_node2: Rc<RefCell<BstNode>>;
+        let mut y_node2: Rc<RefCell<BstNode<K, V, M>>>;
         while BstNode::is_nil(&y_node) && BstNode::is_node_match_option(Some(x_node.clone()), y_node_right.clone()) {
             y_node2 = y_node.clone().unwrap();
             x_node = &y_node2;
@@ -428,18 +1097,18 @@ impl BstNode {
         }
 
         //in case our sucessor traversal yield root, means self is the highest key
-        if BstNode::is_node_match_option(y_node.clone(), Some(BstNode::get_root(&x_node))) {
+        if BstNode::is_node_match_option(y_node.clone(), Some(BstNode::get_root(x_node))) {
             return None;
         }
 
         //default return self / x_node
-        return Some(y_node.clone().unwrap())
+        Some(y_node.clone().unwrap())
     }
 
     /**
      * private function return true if node doesn't has parent nor children nor key
      */
-    fn is_nil(node: &Option<BstNodeLink>) -> bool {
+    fn is_nil(node: &Option<BstNodeLink<K, V, M>>) -> bool {
         match node {
             None => true,
             Some(x) => {
@@ -449,36 +1118,120 @@ impl BstNode {
                 {
                     return true;
                 }
-                return false;
+                false
             }
         }
     }
 
-    //helper function to compare both nodelink
-    fn is_node_match_option(node1: Option<BstNodeLink>, node2: Option<BstNodeLink>) -> bool {
+    //helper function to compare both nodelink by identity
+    fn is_node_match_option(node1: Option<BstNodeLink<K, V, M>>, node2: Option<BstNodeLink<K, V, M>>) -> bool {
         if node1.is_none() && node2.is_none() {
             return true;
         }
         if let Some(node1v) = node1 {
-            return node2.is_some_and(|x: BstNodeLink| x.borrow().key == node1v.borrow().key);
+            return node2.is_some_and(|x: BstNodeLink<K, V, M>| BstNode::is_node_match(&x, &node1v));
         }
-        return false;
+        false
     }
 
-    fn is_node_match(anode: &BstNodeLink, bnode: &BstNodeLink) -> bool {
-        if anode.borrow().key == bnode.borrow().key {
-            return true;
-        }
-        return false;
+    //the nodes are compared by identity, with duplicate keys two different nodes may share the key
+    pub fn is_node_match(anode: &BstNodeLink<K, V, M>, bnode: &BstNodeLink<K, V, M>) -> bool {
+        Rc::ptr_eq(anode, bnode)
+    }
+
+    //whether node hangs in the left slot of parent
+    pub fn is_left_child(parent: &BstNodeLink<K, V, M>, node: &BstNodeLink<K, V, M>) -> bool {
+        parent.borrow().left.as_ref().is_some_and(|left| BstNode::is_node_match(left, node))
     }
 
     /**
      * As the name implied, used to upgrade parent node to strong nodelink
      */
-    fn upgrade_weak_to_strong(node: Option<WeakBstNodeLink>) -> Option<BstNodeLink> {
+    pub fn upgrade_weak_to_strong(node: Option<WeakBstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
         match node {
             None => None,
-            Some(x) => Some(x.upgrade()).unwrap(),
+            Some(x) => x.upgrade(),
+        }
+    }
+}
+
+/**
+ * What the tests of every balanced variant check, each variant adds the invariants of its own shape on top
+ */
+#[cfg(test)]
+pub(crate) mod checks {
+    use std::collections::BTreeSet;
+
+    use crate::structure::balance::{BalancePolicy, BalancedTree};
+    use crate::structure::binary_search_tree::BinarySearchTree;
+    use crate::structure::bst::{BstNode, NodeMeta};
+
+    //a xorshift of its own, so the tests of the variants don't lean on the random generator of the treap
+    pub(crate) struct TestRng {
+        state: u64,
+    }
+
+    impl TestRng {
+        pub(crate) fn new(seed: u64) -> Self {
+            TestRng { state: seed.max(1) }
+        }
+
+        pub(crate) fn next_u32(&mut self) -> u32 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+
+            (self.state >> 32) as u32
+        }
+    }
+
+    /**
+     * Every child links back to its parent, every size counts its subtree and the root has no parent,
+     * the keys come out of the tree in order and are exactly the ones of the model
+     */
+    pub(crate) fn check_links<V, M: NodeMeta<u32, V>>(tree: &BinarySearchTree<u32, V, M>, keys: &BTreeSet<u32>) {
+        assert_eq!(tree.len(), keys.len());
+        assert!(tree.iter().eq(keys.iter().copied()));
+
+        let mut stack: Vec<_> = tree.root().into_iter().collect();
+
+        if let Some(root) = stack.first() {
+            assert!(root.borrow().parent.is_none(), "the root links to a parent");
+        }
+
+        while let Some(x_node) = stack.pop() {
+            let node = x_node.borrow();
+
+            for child in node.left.iter().chain(node.right.iter()) {
+                let parent = BstNode::upgrade_weak_to_strong(child.borrow().parent.clone()).expect("a child links to its parent");
+                assert!(BstNode::is_node_match(&parent, &x_node), "a child of the node {:?} links to another parent", node.key);
+
+                stack.push(child.clone());
+            }
+
+            assert_eq!(node.size, 1 + BstNode::size_of(&node.left) + BstNode::size_of(&node.right), "the node {:?} keeps a stale size", node.key);
+        }
+    }
+
+    /**
+     * 3000 random inserts and deletes of keys below 300, a third of them deletes, each one matched against a model
+     * The links are checked after every step, then check takes the invariants of the variant
+     */
+    pub(crate) fn random_inserts_and_deletes<P: BalancePolicy<u32, ()>>(tree: &mut BalancedTree<u32, (), P>, seed: u64, check: impl Fn(&BalancedTree<u32, (), P>)) {
+        let mut rng = TestRng::new(seed);
+        let mut keys = BTreeSet::new();
+
+        for _ in 0..3000 {
+            let key = rng.next_u32() % 300;
+
+            if rng.next_u32().is_multiple_of(3) {
+                assert_eq!(tree.delete(&key).is_some(), keys.remove(&key));
+            } else {
+                assert_eq!(tree.insert(key), keys.insert(key));
+            }
+
+            check_links(tree, &keys);
+            check(tree);
         }
     }
 }
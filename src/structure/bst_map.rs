@@ -0,0 +1,122 @@
+use std::cell::{Ref, RefMut};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::BstNodeLink;
+use crate::structure::entry::Entry;
+
+/**
+ * Sorted key/value map, every entry lives in a node of the BST
+ * Insertion and deletion go through the same node operations of the bst module
+ */
+#[derive(Debug)]
+pub struct BstMap<K, V> {
+    tree: BinarySearchTree<K, V>,
+}
+
+impl<K, V> Default for BstMap<K, V> {
+    fn default() -> Self {
+        BstMap { tree: BinarySearchTree::default() }
+    }
+}
+
+impl<K: Ord + Debug, V> BstMap<K, V> {
+    pub fn new() -> Self {
+        BstMap::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    /**
+     * Insert a key with its value, return the old value if the key already existed
+     * The key of the existing node is kept, only the value is swapped
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.tree.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<ValueRef<'_, K, V>> {
+        let node = self.search(key)?;
+
+        Some(ValueRef { node, map: PhantomData })
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<ValueMut<'_, K, V>> {
+        let node = self.search(key)?;
+
+        Some(ValueMut { node, map: PhantomData })
+    }
+
+    /**
+     * Remove the entry of the key, return its value if it existed
+     * The map never hands out node links, ValueRef and ValueMut borrow the map,
+     * so the deleted node is always owned by this call alone
+     */
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.tree.delete(key)?;
+
+        Some(
+            Rc::try_unwrap(node)
+                .ok()
+                .expect("The deleted node of the map is still linked")
+                .into_inner()
+                .value,
+        )
+    }
+
+    fn search(&self, key: &K) -> Option<BstNodeLink<K, V>> {
+        self.tree.search(key)
+    }
+}
+
+/**
+ * The value of a key, it holds the node and borrows the map, so the map can't change while it's alive
+ * The value is borrowed through the RefCell of the node
+ */
+pub struct ValueRef<'a, K, V> {
+    node: BstNodeLink<K, V>,
+    map: PhantomData<&'a BstMap<K, V>>,
+}
+
+impl<K, V> ValueRef<'_, K, V> {
+    pub fn borrow(&self) -> Ref<'_, V> {
+        Ref::map(self.node.borrow(), |node| &node.value)
+    }
+}
+
+/**
+ * Same as ValueRef, except it borrows the map mutably so the value can be changed in place
+ */
+pub struct ValueMut<'a, K, V> {
+    node: BstNodeLink<K, V>,
+    map: PhantomData<&'a mut BstMap<K, V>>,
+}
+
+impl<K, V> ValueMut<'_, K, V> {
+    pub fn borrow(&self) -> Ref<'_, V> {
+        Ref::map(self.node.borrow(), |node| &node.value)
+    }
+
+    pub fn borrow_mut(&mut self) -> RefMut<'_, V> {
+        RefMut::map(self.node.borrow_mut(), |node| &mut node.value)
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+
+/**
+ * Read-only cursor pointing at a node of a BST, or at nothing
+ * The moves follow the child links and the parent weak links of the nodes, so no move searches from the root
+ * A move that has nowhere to go returns false and leaves the cursor where it was
+ */
+pub struct Cursor<'a, K, V = (), M = ()> {
+    tree: &'a BinarySearchTree<K, V, M>,
+    current: Option<BstNodeLink<K, V, M>>,
+}
+
+/**
+ * Cursor that can also delete the current node or insert a new one next to it
+ */
+pub struct CursorMut<'a, K, V = (), M = ()> {
+    tree: &'a mut BinarySearchTree<K, V, M>,
+    current: Option<BstNodeLink<K, V, M>>,
+}
+
+//shared moves, each returns the node to move to
+fn next_of<K: Ord + Debug, V, M>(current: &Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+    BstNode::tree_successor(current.as_ref()?)
+}
+
+fn prev_of<K: Ord + Debug, V, M>(current: &Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+    BstNode::tree_predecessor(current.as_ref()?)
+}
+
+fn parent_of<K, V, M>(current: &Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+    current.as_ref()?.borrow().parent.as_ref()?.upgrade()
+}
+
+fn left_of<K, V, M>(current: &Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+    current.as_ref()?.borrow().left.clone()
+}
+
+fn right_of<K, V, M>(current: &Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+    current.as_ref()?.borrow().right.clone()
+}
+
+//move current to the node found by move_to if there's one
+fn step<K, V, M>(
+    current: &mut Option<BstNodeLink<K, V, M>>,
+    move_to: impl FnOnce(&Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>>,
+) -> bool {
+    match move_to(current) {
+        Some(target) => {
+            *current = Some(target);
+            true
+        },
+        None => false,
+    }
+}
+
+impl<'a, K: Ord + Debug, V, M> Cursor<'a, K, V, M> {
+    pub(crate) fn new(tree: &'a BinarySearchTree<K, V, M>, current: Option<BstNodeLink<K, V, M>>) -> Self {
+        Cursor { tree, current }
+    }
+
+    /**
+     * The node the cursor points at, None if it points at nothing
+     */
+    pub fn current(&self) -> Option<BstNodeLink<K, V, M>> {
+        self.current.clone()
+    }
+
+    pub fn key(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.current.as_ref()?.borrow().key.clone()
+    }
+
+    pub fn tree(&self) -> &'a BinarySearchTree<K, V, M> {
+        self.tree
+    }
+
+    //move to the successor
+    pub fn move_next(&mut self) -> bool {
+        step(&mut self.current, next_of)
+    }
+
+    //move to the predecessor
+    pub fn move_prev(&mut self) -> bool {
+        step(&mut self.current, prev_of)
+    }
+
+    pub fn move_parent(&mut self) -> bool {
+        step(&mut self.current, parent_of)
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        step(&mut self.current, left_of)
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        step(&mut self.current, right_of)
+    }
+}
+
+impl<'a, K: Ord + Debug, V, M: NodeMeta<K, V>> CursorMut<'a, K, V, M> {
+    pub(crate) fn new(tree: &'a mut BinarySearchTree<K, V, M>, current: Option<BstNodeLink<K, V, M>>) -> Self {
+        CursorMut { tree, current }
+    }
+
+    pub fn current(&self) -> Option<BstNodeLink<K, V, M>> {
+        self.current.clone()
+    }
+
+    pub fn key(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.current.as_ref()?.borrow().key.clone()
+    }
+
+    /**
+     * Borrow the cursor as a read-only one, the tree stays borrowed by this cursor
+     */
+    pub fn as_cursor(&self) -> Cursor<'_, K, V, M> {
+        Cursor::new(self.tree, self.current.clone())
+    }
+
+    pub fn move_next(&mut self) -> bool {
+        step(&mut self.current, next_of)
+    }
+
+    pub fn move_prev(&mut self) -> bool {
+        step(&mut self.current, prev_of)
+    }
+
+    pub fn move_parent(&mut self) -> bool {
+        step(&mut self.current, parent_of)
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        step(&mut self.current, left_of)
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        step(&mut self.current, right_of)
+    }
+
+    /**
+     * Delete the current node and return it detached, the cursor moves on to its successor
+     * The nodes are relinked on delete, so the successor found beforehand is still in the tree
+     */
+    pub fn remove_current(&mut self) -> Option<BstNodeLink<K, V, M>> {
+        let removed = self.current.take()?;
+        self.current = BstNode::tree_successor(&removed);
+
+        self.tree.remove_node(&removed);
+
+        Some(removed)
+    }
+
+    /**
+     * Insert a new node right after the current one, the cursor stays where it is
+     * Return false if the key does not fall strictly between the current node and its successor
+     * A cursor pointing at nothing only inserts into an empty tree
+     */
+    pub fn insert_after(&mut self, key: K, value: V) -> bool {
+        let Some(current) = &self.current else {
+            return self.insert_into_empty(key, value);
+        };
+
+        let fits = current.borrow().key.as_ref().is_some_and(|current_key| &key > current_key)
+            && BstNode::tree_successor(current).is_none_or(|next| next.borrow().key.as_ref().is_some_and(|next_key| &key < next_key));
+
+        if !fits {
+            debug!("- The key {:?} does not fit right after the node {:?}, failed to insert", key, current.borrow().key);
+
+            return false;
+        }
+
+        BstNode::tree_insert_after(current, key, value);
+        self.tree.grow();
+        true
+    }
+
+    /**
+     * Same as insert_after, except the new node goes right before the current one
+     */
+    pub fn insert_before(&mut self, key: K, value: V) -> bool {
+        let Some(current) = &self.current else {
+            return self.insert_into_empty(key, value);
+        };
+
+        let fits = current.borrow().key.as_ref().is_some_and(|current_key| &key < current_key)
+            && BstNode::tree_predecessor(current).is_none_or(|prev| prev.borrow().key.as_ref().is_some_and(|prev_key| &key > prev_key));
+
+        if !fits {
+            debug!("- The key {:?} does not fit right before the node {:?}, failed to insert", key, current.borrow().key);
+
+            return false;
+        }
+
+        BstNode::tree_insert_before(current, key, value);
+        self.tree.grow();
+        true
+    }
+
+    fn insert_into_empty(&mut self, key: K, value: V) -> bool {
+        if !self.tree.is_empty() {
+            debug!("- The cursor does not point at any node, failed to insert {:?}", key);
+
+            return false;
+        }
+
+        self.tree.insert_with_value(key, value)
+    }
+}
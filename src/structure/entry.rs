@@ -0,0 +1,139 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::{BstNode, BstNodeLink, InsertSlot, NodeMeta};
+
+/**
+ * A view into a single key of a BST, found by one descent from the root
+ * Either the node with the key exists, or the empty slot where it belongs is remembered
+ */
+pub enum Entry<'a, K, V = (), M = ()> {
+    Occupied(OccupiedEntry<'a, K, V, M>),
+    Vacant(VacantEntry<'a, K, V, M>),
+}
+
+/**
+ * key is the one the entry was asked for, the node keeps its own equal key
+ */
+pub struct OccupiedEntry<'a, K, V = (), M = ()> {
+    tree: &'a mut BinarySearchTree<K, V, M>,
+    key: K,
+    node: BstNodeLink<K, V, M>,
+}
+
+/**
+ * slot is the parent of the new node along with the side, None when the tree is empty
+ */
+pub struct VacantEntry<'a, K, V = (), M = ()> {
+    tree: &'a mut BinarySearchTree<K, V, M>,
+    key: K,
+    slot: Option<InsertSlot<K, V, M>>,
+}
+
+impl<'a, K: Ord + Debug, V, M: NodeMeta<K, V>> Entry<'a, K, V, M> {
+    pub(crate) fn new(tree: &'a mut BinarySearchTree<K, V, M>, key: K) -> Self {
+        let located = tree.root().map(|root| BstNode::tree_locate(&root, &key));
+
+        match located {
+            Some(Ok(node)) => Entry::Occupied(OccupiedEntry { tree, key, node }),
+            Some(Err(slot)) => Entry::Vacant(VacantEntry { tree, key, slot: Some(slot) }),
+            None => Entry::Vacant(VacantEntry { tree, key, slot: None }),
+        }
+    }
+
+    /**
+     * Return the node of the key, the node is inserted with the value first if it's vacant
+     */
+    pub fn or_insert(self, value: V) -> BstNodeLink<K, V, M> {
+        self.or_insert_with(|| value)
+    }
+
+    //same as or_insert, except the value is only made when it's needed
+    pub fn or_insert_with<F: FnOnce() -> V>(self, f: F) -> BstNodeLink<K, V, M> {
+        match self {
+            Entry::Occupied(entry) => entry.into_node(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    pub fn or_default(self) -> BstNodeLink<K, V, M>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /**
+     * Change the value in place if the key exists, a vacant entry is left as it is
+     * The meta from the node up to the root is refreshed afterwards, in case it's made from the values
+     */
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &self {
+            f(&mut entry.node.borrow_mut().value);
+            BstNode::resize_upward(Some(entry.node.clone()));
+        }
+
+        self
+    }
+}
+
+impl<'a, K: Ord + Debug, V, M: NodeMeta<K, V>> OccupiedEntry<'a, K, V, M> {
+    pub fn node(&self) -> BstNodeLink<K, V, M> {
+        self.node.clone()
+    }
+
+    pub fn into_node(self) -> BstNodeLink<K, V, M> {
+        self.node
+    }
+
+    //give back the key the entry was asked for along with the node
+    pub fn into_parts(self) -> (K, BstNodeLink<K, V, M>) {
+        (self.key, self.node)
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        let old = std::mem::replace(&mut self.node.borrow_mut().value, value);
+        BstNode::resize_upward(Some(self.node.clone()));
+
+        old
+    }
+
+    /**
+     * Delete the node of the entry, return it detached
+     */
+    pub fn remove(self) -> BstNodeLink<K, V, M> {
+        self.tree.remove_node(&self.node);
+        self.node
+    }
+}
+
+impl<'a, K: Ord + Debug, V, M: NodeMeta<K, V>> VacantEntry<'a, K, V, M> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /**
+     * Attach the new node right at the slot found by the descent, no search happens again
+     */
+    pub fn insert(self, value: V) -> BstNodeLink<K, V, M> {
+        let node = match self.slot {
+            Some((y_node, is_left)) => BstNode::tree_insert_at(&y_node, is_left, self.key, value),
+            None => {
+                debug!("- The tree is empty, the node {:?} becomes the root", self.key);
+
+                let root = BstNode::new_bst_nodelink_with_value(self.key, value);
+                self.tree.set_root(root.clone());
+                root
+            }
+        };
+
+        self.tree.grow();
+        node
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicatePolicy};
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use crate::tool::DotStyle;
+
+/**
+ * Closed interval [lo, hi], a single point is the interval [p, p]
+ * The ends are only set by new, so lo <= hi always holds
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval<K> {
+    lo: K,
+    hi: K,
+}
+
+impl<K> Interval<K> {
+    pub fn lo(&self) -> &K {
+        &self.lo
+    }
+
+    pub fn hi(&self) -> &K {
+        &self.hi
+    }
+}
+
+impl<K: Ord + Debug> Interval<K> {
+    pub fn new(lo: K, hi: K) -> Self {
+        assert!(lo <= hi, "the interval [{:?}, {:?}] ends before it starts", lo, hi);
+
+        Interval { lo, hi }
+    }
+
+    //both ends are included, so intervals that only touch at an end overlap
+    pub fn overlaps(&self, other: &Interval<K>) -> bool {
+        self.lo <= other.hi && other.lo <= self.hi
+    }
+
+    pub fn contains(&self, point: &K) -> bool {
+        &self.lo <= point && point <= &self.hi
+    }
+}
+
+impl<K: Clone> From<K> for Interval<K> {
+    fn from(point: K) -> Self {
+        Interval { lo: point.clone(), hi: point }
+    }
+}
+
+/**
+ * The largest hi of the intervals in the subtree of a node, None only before the node gets its interval
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxHi<K>(pub Option<K>);
+
+impl<K> Default for MaxHi<K> {
+    fn default() -> Self {
+        MaxHi(None)
+    }
+}
+
+//the key of a node is its lo and the value is its hi
+impl<K: Ord + Clone> NodeMeta<K, K> for MaxHi<K> {
+    fn refresh(&mut self, _lo: &K, hi: &K, left: Option<&Self>, right: Option<&Self>) {
+        let below = [left, right].into_iter().flatten().filter_map(|child| child.0.as_ref());
+
+        self.0 = below.chain([hi]).max().cloned();
+    }
+}
+
+impl<K: Debug> DotStyle for MaxHi<K> {
+    fn dot_label(&self) -> Option<String> {
+        self.0.as_ref().map(|max_hi| format!("max {:?}", max_hi))
+    }
+}
+
+type IntervalNodeLink<K> = BstNodeLink<K, K, MaxHi<K>>;
+
+/**
+ * Interval tree according to the book, every node holds an interval keyed by its lo along with its hi as the value,
+ * the meta keeps the largest hi of the subtree so whole subtrees that end too early are skipped by the queries
+ * The max hi is refreshed by the node operations of the bst module wherever the sizes are, so it stays right
+ * through every insert, delete, transplant and rotation
+ * Intervals sharing their lo each get a node of their own, the tree is a multiset by lo
+ * No link to a node is handed out, a hi changed through a link would leave the max hi above it stale,
+ * so the intervals only come out as values
+ */
+#[derive(Debug)]
+pub struct IntervalTree<K> {
+    tree: BinarySearchTree<K, K, MaxHi<K>>,
+}
+
+impl<K: Ord + Debug + Clone> Default for IntervalTree<K> {
+    fn default() -> Self {
+        IntervalTree { tree: BinarySearchTree::with_policy(DuplicatePolicy::Multiset) }
+    }
+}
+
+impl<K: Ord + Debug + Clone> FromIterator<Interval<K>> for IntervalTree<K> {
+    fn from_iter<I: IntoIterator<Item = Interval<K>>>(iter: I) -> Self {
+        let mut tree = IntervalTree::new();
+
+        for interval in iter {
+            tree.insert_interval(interval);
+        }
+
+        tree
+    }
+}
+
+impl<K: Ord + Debug + Clone> IntervalTree<K> {
+    pub fn new() -> Self {
+        IntervalTree::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /**
+     * Insert the interval as a new node and return it as stored, an interval that is already in the tree gets another node
+     */
+    pub fn insert_interval(&mut self, interval: Interval<K>) -> Interval<K> {
+        debug!("- Insert the interval [{:?}, {:?}]", interval.lo, interval.hi);
+
+        match self.tree.try_insert_with_value(interval.lo, interval.hi) {
+            Ok(node) => IntervalTree::interval_of(&node),
+            Err(_) => unreachable!("the Multiset policy takes every key"),
+        }
+    }
+
+    /**
+     * Delete a node holding exactly the interval, return its interval or None if there's no such interval
+     */
+    pub fn delete_interval(&mut self, interval: &Interval<K>) -> Option<Interval<K>> {
+        let node = self.find_interval(interval)?;
+        self.tree.remove_node(&node);
+
+        Some(IntervalTree::interval_of(&node))
+    }
+
+    pub fn contains_interval(&self, interval: &Interval<K>) -> bool {
+        self.find_interval(interval).is_some()
+    }
+
+    //the intervals in the order of their lo
+    pub fn iter(&self) -> impl Iterator<Item = Interval<K>> + '_ {
+        std::iter::successors(self.tree.minimum(), BstNode::tree_successor).map(|node| IntervalTree::interval_of(&node))
+    }
+
+    /**
+     * The largest hi of all the intervals, None for the empty tree
+     */
+    pub fn max_hi(&self) -> Option<K> {
+        self.tree.root()?.borrow().meta.0.clone()
+    }
+
+    /**
+     * INTERVAL-SEARCH, find an interval that overlaps the query, either a point or an interval, in O(h)
+     * Going left is safe whenever the left subtree reaches the query, if none of it overlaps then nothing
+     * on the right does either, since everything there starts later still
+     */
+    pub fn overlaps(&self, query: impl Into<Interval<K>>) -> Option<Interval<K>> {
+        let query = query.into();
+
+        debug!("- Search an interval overlapping [{:?}, {:?}]", query.lo, query.hi);
+
+        let mut x_node = self.tree.root();
+
+        while let Some(current) = x_node {
+            let interval = IntervalTree::interval_of(&current);
+
+            if interval.overlaps(&query) {
+                debug!("- The interval [{:?}, {:?}] of the node overlaps the query", interval.lo, interval.hi);
+
+                return Some(interval);
+            }
+
+            let left = current.borrow().left.clone();
+
+            x_node = match left {
+                Some(left) if IntervalTree::reaches(&left, &query.lo) => {
+                    debug!("- The left subtree of the node {:?} ends as late as {:?}, go left", current.borrow().key, left.borrow().meta.0);
+
+                    Some(left)
+                },
+                _ => {
+                    debug!("- The left subtree of the node {:?} ends before the query starts, go right", current.borrow().key);
+
+                    current.borrow().right.clone()
+                }
+            };
+        }
+
+        debug!("- No interval overlaps the query");
+
+        None
+    }
+
+    /**
+     * Every interval overlapping the query, in the order of their lo
+     * Subtrees that end before the query starts and right subtrees that start after it ends are skipped,
+     * so it takes O(min(n, k log n)) on a balanced shape for k intervals found
+     */
+    pub fn all_overlapping(&self, query: impl Into<Interval<K>>) -> Vec<Interval<K>> {
+        let query = query.into();
+        let mut found = Vec::new();
+
+        debug!("- Collect the intervals overlapping [{:?}, {:?}]", query.lo, query.hi);
+
+        if let Some(root) = self.tree.root() {
+            IntervalTree::collect_overlapping(&root, &query, &mut found);
+        }
+
+        found
+    }
+
+    /**
+     * Rotate at the node holding exactly the interval as BinarySearchTree::rotate_left does,
+     * the max hi of both nodes is refreshed along the way
+     * Return the interval now on top, or None if there's no such interval or nothing to rotate up
+     */
+    pub fn rotate_left(&mut self, interval: &Interval<K>) -> Option<Interval<K>> {
+        let node = self.find_interval(interval)?;
+        let top = self.tree.rotate_left(&node)?;
+
+        Some(IntervalTree::interval_of(&top))
+    }
+
+    pub fn rotate_right(&mut self, interval: &Interval<K>) -> Option<Interval<K>> {
+        let node = self.find_interval(interval)?;
+        let top = self.tree.rotate_right(&node)?;
+
+        Some(IntervalTree::interval_of(&top))
+    }
+
+    fn interval_of(node: &IntervalNodeLink<K>) -> Interval<K> {
+        let node = node.borrow();
+        let lo = node.key.clone().expect("a node in the tree has a key");
+
+        Interval { lo, hi: node.value.clone() }
+    }
+
+    //true if some interval in the subtree of node ends at lo or later
+    fn reaches(node: &IntervalNodeLink<K>, lo: &K) -> bool {
+        node.borrow().meta.0.as_ref().is_some_and(|max_hi| max_hi >= lo)
+    }
+
+    //the occurrences of lo sit next to each other in order, walk them from the first one
+    fn find_interval(&self, interval: &Interval<K>) -> Option<IntervalNodeLink<K>> {
+        let mut x_node = match self.tree.lower(&interval.lo) {
+            Some(lower) => BstNode::tree_successor(&lower),
+            None => self.tree.minimum(),
+        };
+
+        while let Some(current) = x_node {
+            if current.borrow().key.as_ref() != Some(&interval.lo) {
+                break;
+            }
+
+            if current.borrow().value == interval.hi {
+                return Some(current);
+            }
+
+            x_node = BstNode::tree_successor(&current);
+        }
+
+        debug!("- The interval [{:?}, {:?}] is not in the tree", interval.lo, interval.hi);
+
+        None
+    }
+
+    fn collect_overlapping(x_node: &IntervalNodeLink<K>, query: &Interval<K>, found: &mut Vec<Interval<K>>) {
+        if !IntervalTree::reaches(x_node, &query.lo) {
+            debug!("- The subtree of the node {:?} ends before the query starts, skip it", x_node.borrow().key);
+
+            return;
+        }
+
+        let left = x_node.borrow().left.clone();
+        if let Some(left) = left {
+            IntervalTree::collect_overlapping(&left, query, found);
+        }
+
+        let interval = IntervalTree::interval_of(x_node);
+        let starts_in_time = interval.lo <= query.hi;
+
+        if interval.overlaps(query) {
+            found.push(interval);
+        }
+
+        //everything on the right starts no earlier than x_node, so it's out of reach if x_node starts too late
+        let right = x_node.borrow().right.clone();
+        if let Some(right) = right.filter(|_| starts_in_time) {
+            IntervalTree::collect_overlapping(&right, query, found);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::Bound;
+
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::BstNodeLink;
+
+/**
+ * In-order iterator over the keys that fall inside a range
+ * The stack holds the nodes whose key is yet to be yielded, the left subtree of a node below
+ * the start bound and everything after the first key beyond the end bound are never visited
+ */
+pub struct Range<'a, K, V = (), M = ()> {
+    stack: Vec<BstNodeLink<K, V, M>>,
+    start: Bound<K>,
+    end: Bound<K>,
+    tree: PhantomData<&'a BinarySearchTree<K, V, M>>,
+}
+
+impl<K: Ord + Clone, V, M> Range<'_, K, V, M> {
+    pub(crate) fn new(root: Option<&BstNodeLink<K, V, M>>, start: Bound<K>, end: Bound<K>) -> Self {
+        let mut range = Range { stack: Vec::new(), start, end, tree: PhantomData };
+        range.push_left_spine(root.cloned());
+        range
+    }
+
+    //walk down to the left, skipping the nodes that are below the start bound
+    fn push_left_spine(&mut self, mut x_node: Option<BstNodeLink<K, V, M>>) {
+        while let Some(current) = x_node {
+            let below_start = match (&current.borrow().key, &self.start) {
+                (Some(key), Bound::Included(start)) => key < start,
+                (Some(key), Bound::Excluded(start)) => key <= start,
+                _ => false,
+            };
+
+            if below_start {
+                x_node = current.borrow().right.clone();
+            } else {
+                x_node = current.borrow().left.clone();
+                self.stack.push(current);
+            }
+        }
+    }
+
+    fn beyond_end(&self, key: &K) -> bool {
+        match &self.end {
+            Bound::Included(end) => key > end,
+            Bound::Excluded(end) => key >= end,
+            Bound::Unbounded => false,
+        }
+    }
+}
+
+impl<K: Ord + Clone, V, M> Iterator for Range<'_, K, V, M> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let x_node = self.stack.pop()?;
+        let key = x_node.borrow().key.clone()?;
+
+        if self.beyond_end(&key) {
+            self.stack.clear();
+            return None;
+        }
+
+        self.push_left_spine(x_node.borrow().right.clone());
+        Some(key)
+    }
+}
+
+/**
+ * In-order iterator over all the keys, next walks up from the minimum and next_back walks down from the maximum
+ * Both ends share the count of the keys left, so they stop once they meet
+ * Like every iterator here but IntoIter, it borrows the tree, so the tree can't change while it's alive
+ */
+pub struct Iter<'a, K, V = (), M = ()> {
+    front: Vec<BstNodeLink<K, V, M>>,
+    back: Vec<BstNodeLink<K, V, M>>,
+    remaining: usize,
+    tree: PhantomData<&'a BinarySearchTree<K, V, M>>,
+}
+
+impl<K: Clone, V, M> Iter<'_, K, V, M> {
+    pub(crate) fn new(root: Option<&BstNodeLink<K, V, M>>, len: usize) -> Self {
+        let mut iter = Iter { front: Vec::new(), back: Vec::new(), remaining: len, tree: PhantomData };
+        push_spine(&mut iter.front, root.cloned(), |node| node.borrow().left.clone());
+        push_spine(&mut iter.back, root.cloned(), |node| node.borrow().right.clone());
+        iter
+    }
+}
+
+//walk down one side, pushing every node on the way
+fn push_spine<K, V, M>(
+    stack: &mut Vec<BstNodeLink<K, V, M>>,
+    mut x_node: Option<BstNodeLink<K, V, M>>,
+    child: impl Fn(&BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>>,
+) {
+    while let Some(current) = x_node {
+        x_node = child(&current);
+        stack.push(current);
+    }
+}
+
+impl<K: Clone, V, M> Iterator for Iter<'_, K, V, M> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let x_node = self.front.pop()?;
+        let key = x_node.borrow().key.clone();
+        push_spine(&mut self.front, x_node.borrow().right.clone(), |node| node.borrow().left.clone());
+        self.remaining -= 1;
+
+        key
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Clone, V, M> DoubleEndedIterator for Iter<'_, K, V, M> {
+    fn next_back(&mut self) -> Option<K> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let x_node = self.back.pop()?;
+        let key = x_node.borrow().key.clone();
+        push_spine(&mut self.back, x_node.borrow().left.clone(), |node| node.borrow().right.clone());
+        self.remaining -= 1;
+
+        key
+    }
+}
+
+impl<K: Clone, V, M> ExactSizeIterator for Iter<'_, K, V, M> {}
+
+/**
+ * Pre-order iterator, a node comes before both of its subtrees
+ */
+pub struct Preorder<'a, K, V = (), M = ()> {
+    stack: Vec<BstNodeLink<K, V, M>>,
+    tree: PhantomData<&'a BinarySearchTree<K, V, M>>,
+}
+
+impl<K: Clone, V, M> Preorder<'_, K, V, M> {
+    pub(crate) fn new(root: Option<&BstNodeLink<K, V, M>>) -> Self {
+        Preorder { stack: root.into_iter().cloned().collect(), tree: PhantomData }
+    }
+}
+
+impl<K: Clone, V, M> Iterator for Preorder<'_, K, V, M> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let x_node = self.stack.pop()?;
+        let current = x_node.borrow();
+
+        //the right child is pushed first so the left subtree is visited first
+        self.stack.extend(current.right.clone());
+        self.stack.extend(current.left.clone());
+
+        current.key.clone()
+    }
+}
+
+/**
+ * Post-order iterator, a node comes after both of its subtrees
+ * A node is pushed back as expanded before its children, so it pops again once they are done
+ */
+pub struct Postorder<'a, K, V = (), M = ()> {
+    stack: Vec<(BstNodeLink<K, V, M>, bool)>,
+    tree: PhantomData<&'a BinarySearchTree<K, V, M>>,
+}
+
+impl<K: Clone, V, M> Postorder<'_, K, V, M> {
+    pub(crate) fn new(root: Option<&BstNodeLink<K, V, M>>) -> Self {
+        Postorder { stack: root.into_iter().map(|root| (root.clone(), false)).collect(), tree: PhantomData }
+    }
+}
+
+impl<K: Clone, V, M> Iterator for Postorder<'_, K, V, M> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        loop {
+            let (x_node, expanded) = self.stack.pop()?;
+
+            if expanded {
+                let key = x_node.borrow().key.clone();
+                return key;
+            }
+
+            let right = x_node.borrow().right.clone();
+            let left = x_node.borrow().left.clone();
+
+            self.stack.push((x_node, true));
+            self.stack.extend(right.map(|right| (right, false)));
+            self.stack.extend(left.map(|left| (left, false)));
+        }
+    }
+}
+
+/**
+ * Level-order iterator, the nodes are visited depth by depth from the root, left to right
+ */
+pub struct LevelOrder<'a, K, V = (), M = ()> {
+    queue: VecDeque<BstNodeLink<K, V, M>>,
+    tree: PhantomData<&'a BinarySearchTree<K, V, M>>,
+}
+
+impl<K: Clone, V, M> LevelOrder<'_, K, V, M> {
+    pub(crate) fn new(root: Option<&BstNodeLink<K, V, M>>) -> Self {
+        LevelOrder { queue: root.into_iter().cloned().collect(), tree: PhantomData }
+    }
+}
+
+impl<K: Clone, V, M> Iterator for LevelOrder<'_, K, V, M> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let x_node = self.queue.pop_front()?;
+        let current = x_node.borrow();
+
+        self.queue.extend(current.left.clone());
+        self.queue.extend(current.right.clone());
+
+        current.key.clone()
+    }
+}
+
+/**
+ * Owning in-order iterator, the keys are taken out of the nodes, which are cut off from their children
+ * on the way, so the nodes are freed one by one even if some links to them are still held outside
+ */
+pub struct IntoIter<K, V = (), M = ()> {
+    stack: Vec<BstNodeLink<K, V, M>>,
+    remaining: usize,
+}
+
+impl<K, V, M> IntoIter<K, V, M> {
+    pub(crate) fn new(root: Option<BstNodeLink<K, V, M>>, len: usize) -> Self {
+        let mut iter = IntoIter { stack: Vec::new(), remaining: len };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut x_node: Option<BstNodeLink<K, V, M>>) {
+        while let Some(current) = x_node {
+            x_node = current.borrow_mut().left.take();
+            self.stack.push(current);
+        }
+    }
+}
+
+impl<K, V, M> Iterator for IntoIter<K, V, M> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let x_node = self.stack.pop()?;
+        let right = x_node.borrow_mut().right.take();
+        self.push_left_spine(right);
+        self.remaining -= 1;
+
+        let key = x_node.borrow_mut().key.take();
+        key
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, M> ExactSizeIterator for IntoIter<K, V, M> {}
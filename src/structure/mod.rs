@@ -1,4 +1,20 @@
+pub mod aa;
+pub mod aggregate;
+pub mod avl;
+pub mod balance;
+pub mod binary_search_tree;
 pub mod bst;
+pub mod bst_map;
+pub mod cursor;
+pub mod entry;
+pub mod interval;
+pub mod iter;
+pub mod persistent;
+pub mod red_black;
+pub mod scapegoat;
+pub mod splay;
+pub mod treap;
+pub mod weight_balanced;
 pub mod tree {
     use std::cell::RefCell;
     use std::rc::{Rc, Weak};
@@ -27,8 +43,7 @@ pub mod tree {
 
         pub fn new_nodelink(value: i32) -> NodeLink {
             let currentnode = Node::new(value);
-            let currentlink = Rc::new(RefCell::new(currentnode));
-            currentlink
+            Rc::new(RefCell::new(currentnode))
         }
 
         /**
@@ -50,13 +65,11 @@ pub mod tree {
         fn new_with_parent(parent: &NodeLink, value: i32) -> NodeLink {
             let mut currentnode = Node::new(value);
             currentnode.add_parent(Rc::<RefCell<Node>>::downgrade(parent));
-            let currentlink = Rc::new(RefCell::new(currentnode));
-            currentlink
+            Rc::new(RefCell::new(currentnode))
         }
 
         fn new_from_node(node: Node) -> NodeLink {
-            let currentlink = Rc::new(RefCell::new(node));
-            currentlink
+            Rc::new(RefCell::new(node))
         }
 
         //add new left child, set the parent to current_node_link
@@ -80,10 +93,7 @@ pub mod tree {
          * As the name implied, used to upgrade parent node to strong nodelink
          */
         pub fn upgrade_weak_to_strong(node: Option<WeakNodeLink>) -> Option<NodeLink> {
-            match node {
-                None => None,
-                Some(x) => Some(x.upgrade().unwrap()),
-            }
+            node.map(|x| x.upgrade().unwrap())
         }
 
         /**
@@ -91,102 +101,100 @@ pub mod tree {
          */
         #[allow(dead_code)]
         fn is_node_match_weak_strong(node1: Option<WeakNodeLink>, node2: Option<NodeLink>) -> bool {
-            let node1s: Option<Rc<RefCell<Node>>> = Node::upgrade_weak_to_strong(node1);
-            if node1s.is_none() && node2.is_none() {
-                return true;
-            }
-            if let Some(node1sv) = node1s {
-                return node2.is_some_and(|x: NodeLink| x.borrow().value == node1sv.borrow().value);
-            }
-            false
+            Node::is_node_match_both_weak(node1, node2.as_ref().map(Rc::downgrade))
         }
 
-        //helper function
+        //helper function, both weak links are compared by the node they point at, no need to upgrade
         fn is_node_match_both_weak(
             node1: Option<WeakNodeLink>,
             node2: Option<WeakNodeLink>,
         ) -> bool {
-            let node1s: Option<Rc<RefCell<Node>>> = Node::upgrade_weak_to_strong(node1);
-            let node2s: Option<Rc<RefCell<Node>>> = Node::upgrade_weak_to_strong(node2);
-            if node1s.is_none() && node2s.is_none() {
-                return true;
+            match (node1, node2) {
+                (None, None) => true,
+                (Some(node1v), Some(node2v)) => Weak::ptr_eq(&node1v, &node2v),
+                _ => false,
             }
-            return Node::is_node_match_both_strong(node1s, node2s);
         }
 
-        //helper function to compare both nodelink
+        //helper function to compare both nodelink, two nodes match only if they are the very same node
         fn is_node_match_both_strong(node1: Option<NodeLink>, node2: Option<NodeLink>) -> bool {
-            if node1.is_none() && node2.is_none() {
-                return true;
-            }
-            if let Some(node1v) = node1 {
-                return node2.is_some_and(|x: NodeLink| x.borrow().value == node1v.borrow().value);
+            match (node1, node2) {
+                (None, None) => true,
+                (Some(node1v), Some(node2v)) => Rc::ptr_eq(&node1v, &node2v),
+                _ => false,
             }
-            return false;
         }
 
         /**
          * This function will return the node that match value
+         * It's searched from a copy of self, so a match on self itself comes back as a detached copy
+         */
+        #[deprecated(note = "a match on self is a detached copy, use Node::find_node_by_value to get the node inside the tree")]
+        pub fn get_node_by_value(&self, value: i32) -> Option<NodeLink> {
+            Node::find_node_by_value(&Node::new_from_node(self.clone()), value)
+        }
+
+        /**
+         * This function will return the node that match value
+         * The link points at the node inside the tree, so it can be compared by identity or changed in place
          * Let's assume the tree won't have any value duplicates
          */
-        pub fn get_node_by_value(&self, value: i32) -> Option<NodeLink>{
+        pub fn find_node_by_value(node: &NodeLink, value: i32) -> Option<NodeLink>{
             //check current node value
-            if self.value == value {
-                //create clone of NodeLink
-                let node = self.clone();
-                let nodelink = Rc::new(RefCell::new(node));
-                return Rc::<RefCell<Node>>::downgrade(&nodelink).upgrade();
-            }
-            //go left if exist
-            if let Some(x) = &self.left {
-                return x.borrow().get_node_by_value(value);
+            if node.borrow().value == value {
+                return Some(node.clone());
             }
-            if let Some(x) = &self.right {
-                return x.borrow().get_node_by_value(value);
+            //go left if exist, then right if the left subtree does not have it
+            if let Some(found) = node.borrow().left.as_ref().and_then(|x| Node::find_node_by_value(x, value)) {
+                return Some(found);
             }
-            return None;
+            node.borrow().right.as_ref().and_then(|x| Node::find_node_by_value(x, value))
+        }
+
+        /**
+         * This function will return the node that matches all Nodelink Properties of node
+         * Same as get_node_by_value, a match on self itself comes back as a detached copy
+         */
+        #[deprecated(note = "a match on self is a detached copy, use Node::find_node_by_full_property to get the node inside the tree")]
+        pub fn get_node_by_full_property(&self, node: &NodeLink) -> Option<NodeLink> {
+            Node::find_node_by_full_property(&Node::new_from_node(self.clone()), node)
         }
 
         /**
          * This function will return the node that matches all Nodelink Properties:
          * 1). current node value,
-         * 2). node parent value,
-         * 3). both child values
+         * 2). node parent, the very same node,
+         * 3). both childs, the very same nodes
+         * The link points at the node inside the tree, as find_node_by_value does
          * Let's assume the tree won't have any value duplicates
-         * Asssume 2nd parameter is not None
          */
-        pub fn get_node_by_full_property(&self, node: &NodeLink) -> Option<NodeLink> {
+        pub fn find_node_by_full_property(current: &NodeLink, node: &NodeLink) -> Option<NodeLink> {
             //check current node value
             let nodevalue = node.borrow().value;
             let check_parent_eq = Node::is_node_match_both_weak(
                 node.borrow().parent.clone(),
-                self.parent.clone(),
+                current.borrow().parent.clone(),
             );
             let check_left_child_eq = Node::is_node_match_both_strong(
                 node.borrow().left.clone(),
-                self.left.clone(),
+                current.borrow().left.clone(),
             );
             let check_right_child_eq = Node::is_node_match_both_strong(
                 node.borrow().right.clone(),
-                self.right.clone(),
+                current.borrow().right.clone(),
             );
-            if self.value == nodevalue
+            if current.borrow().value == nodevalue
                 && check_parent_eq
                 && check_left_child_eq
                 && check_right_child_eq
             {
-                return Some(self.get_nodelink_copy());
-            } else{
-                //recurse deeper if not found
-                //recurse to left
-                if let Some(left_subtree) = &self.left{
-                    return left_subtree.borrow().get_node_by_full_property(node);
-                } else if let Some(right_subtree) = &self.right{
-                    //recurse to right
-                    return right_subtree.borrow().get_node_by_full_property(node);
-                }
+                return Some(current.clone());
             }
-            None
+            //recurse deeper if not found, to the left first then to the right
+            if let Some(found) = current.borrow().left.as_ref().and_then(|x| Node::find_node_by_full_property(x, node)) {
+                return Some(found);
+            }
+            current.borrow().right.as_ref().and_then(|x| Node::find_node_by_full_property(x, node))
         }
 
         /**
@@ -222,7 +230,7 @@ pub mod tree {
             let mut count = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
             count = Node::count_nodes_by_nodelink(&nodelink, count);
-            return count;
+            count
         }
 
         //the same as above except start the count from nodelink reference parameter
@@ -230,33 +238,32 @@ pub mod tree {
             let mut left_count: i32 = 0;
             let mut right_count: i32 = 0;
             if let Some(left_child) = &node.borrow().left {
-                left_count = Node::count_nodes_by_nodelink(&left_child, count);
+                left_count = Node::count_nodes_by_nodelink(left_child, count);
             }
             if let Some(right_child) = &node.borrow().right {
-                right_count = Node::count_nodes_by_nodelink(&right_child, count);
+                right_count = Node::count_nodes_by_nodelink(right_child, count);
             }
-            return count + left_count + right_count + 1;
+            count + left_count + right_count + 1
         }
 
         /**Count depth of the tree in the current node
          * Count from root is started from 0
          */
         pub fn tree_depth(&self) -> i32 {
-            let depth: i32 = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
-            return self.track_depth(&nodelink, depth);
+            Node::track_depth(&nodelink)
         }
 
         //track depth by traversing all nodes but returned depth count per path. The highest number will be returned
-        fn track_depth(&self, node: &NodeLink, depth: i32) -> i32 {
+        fn track_depth(node: &NodeLink) -> i32 {
             let mut left_depth: i32 = 0;
             let mut right_depth: i32 = 0;
             if let Some(left_child) = &node.borrow().left {
-                left_depth = self.track_depth(left_child, depth) + 1;
+                left_depth = Node::track_depth(left_child) + 1;
             }
 
             if let Some(right_child) = &node.borrow().right {
-                right_depth = self.track_depth(right_child, depth) + 1;
+                right_depth = Node::track_depth(right_child) + 1;
             }
 
             if left_depth > right_depth {
@@ -277,12 +284,12 @@ pub mod tree {
                 let strong_parent = Node::upgrade_weak_to_strong(nodelink.borrow().parent.clone());
                 //check from which child are we
                 if let Some(sparent) = strong_parent {
-                    //check if the left fits nodelink value (we're coming from the left)
+                    //check if the left is nodelink itself (we're coming from the left)
                     if sparent
                         .borrow()
                         .left
                         .as_ref()
-                        .is_some_and(|x| x.borrow().value == nodelink.borrow().value)
+                        .is_some_and(|x| Rc::ptr_eq(x, nodelink))
                     {
                         //return the right node
                         return sparent.clone().borrow().right.clone();
@@ -0,0 +1,536 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use log::debug;
+
+/**
+ * A node of the persistent BST, it never changes once it's built, so it needs no RefCell
+ * There's no parent link, a node is shared by every version that did not touch its subtree,
+ * so it has as many parents as versions share it
+ */
+#[derive(Debug)]
+pub struct PersistentNode<K, V = ()> {
+    key: K,
+    value: V,
+    //number of nodes in the subtree, the node included
+    size: usize,
+    left: Option<PersistentLink<K, V>>,
+    right: Option<PersistentLink<K, V>>,
+}
+
+pub type PersistentLink<K, V = ()> = Rc<PersistentNode<K, V>>;
+
+impl<K, V> PersistentNode<K, V> {
+    //only the tree builds nodes, so a version can never be changed through a node it hands out
+    fn new(key: K, value: V, left: Option<PersistentLink<K, V>>, right: Option<PersistentLink<K, V>>) -> PersistentLink<K, V> {
+        let size = PersistentNode::size_of(&left) + PersistentNode::size_of(&right) + 1;
+
+        Rc::new(PersistentNode { key, value, size, left, right })
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn left(&self) -> Option<&PersistentLink<K, V>> {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> Option<&PersistentLink<K, V>> {
+        self.right.as_ref()
+    }
+
+    pub fn size_of(node: &Option<PersistentLink<K, V>>) -> usize {
+        node.as_ref().map_or(0, |node| node.size)
+    }
+
+    pub fn minimum(node: &PersistentLink<K, V>) -> PersistentLink<K, V> {
+        let mut x_node = node.clone();
+
+        while let Some(left) = x_node.left.clone() {
+            x_node = left;
+        }
+
+        x_node
+    }
+
+    pub fn maximum(node: &PersistentLink<K, V>) -> PersistentLink<K, V> {
+        let mut x_node = node.clone();
+
+        while let Some(right) = x_node.right.clone() {
+            x_node = right;
+        }
+
+        x_node
+    }
+}
+
+/**
+ * Dropping a version frees the nodes only it holds, the children are taken off onto a stack instead of
+ * dropped in place, so a tall path does not recurse as deep as it is
+ */
+impl<K, V> Drop for PersistentNode<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<PersistentLink<K, V>> = self.left.take().into_iter().chain(self.right.take()).collect();
+
+        while let Some(x_node) = stack.pop() {
+            //a node another version still holds stays where it is
+            if let Ok(mut x_node) = Rc::try_unwrap(x_node) {
+                stack.extend(x_node.left.take());
+                stack.extend(x_node.right.take());
+            }
+        }
+    }
+}
+
+impl<K: Clone, V: Clone> PersistentNode<K, V> {
+    //a copy of the node with other children, the copy is what makes the path of an update new
+    fn with_children(node: &PersistentLink<K, V>, left: Option<PersistentLink<K, V>>, right: Option<PersistentLink<K, V>>) -> PersistentLink<K, V> {
+        PersistentNode::new(node.key.clone(), node.value.clone(), left, right)
+    }
+}
+
+/**
+ * One version of a persistent BST, insert and delete leave it as it is and return the next version
+ * Only the nodes on the path to the key are copied, every subtree off the path is shared with the old
+ * version through Rc, so an update costs O(h) new nodes and the old versions stay around for audit or undo
+ * Cloning a version only clones the root link
+ * The keys are unique, inserting a key that is already there replaces its value in the new version
+ */
+#[derive(Debug)]
+pub struct PersistentBst<K, V = ()> {
+    root: Option<PersistentLink<K, V>>,
+    //how many updates led to this version, the empty tree is version 0
+    version: usize,
+}
+
+impl<K, V> Default for PersistentBst<K, V> {
+    fn default() -> Self {
+        PersistentBst { root: None, version: 0 }
+    }
+}
+
+impl<K, V> Clone for PersistentBst<K, V> {
+    fn clone(&self) -> Self {
+        PersistentBst { root: self.root.clone(), version: self.version }
+    }
+}
+
+impl<K: Ord + Debug + Clone> PersistentBst<K, ()> {
+    pub fn insert(&self, key: K) -> Self {
+        self.insert_with_value(key, ())
+    }
+}
+
+impl<K: Ord + Debug + Clone, V: Clone> FromIterator<(K, V)> for PersistentBst<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter().fold(PersistentBst::new(), |tree, (key, value)| tree.insert_with_value(key, value))
+    }
+}
+
+impl<K: Ord + Debug + Clone, V: Clone> PersistentBst<K, V> {
+    /**
+     * The next version with the key, copy the path down to where the key is or should be
+     */
+    pub fn insert_with_value(&self, key: K, value: V) -> Self {
+        debug!("- Insert the key {:?} into the version {}", key, self.version);
+
+        let root = PersistentBst::insert_below(self.root.as_ref(), key, value);
+
+        PersistentBst { root, version: self.version + 1 }
+    }
+
+    /**
+     * The next version without the key, a version that does not have the key at all comes back as it is
+     * A node with both children is replaced by a copy of its successor, as the plain BST does it
+     */
+    pub fn delete(&self, key: &K) -> Self {
+        if !self.contains(key) {
+            debug!("- The key {:?} is not in the version {}, nothing to delete", key, self.version);
+
+            return self.clone();
+        }
+
+        debug!("- Delete the key {:?} from the version {}", key, self.version);
+
+        let root = PersistentBst::delete_below(self.root.as_ref().expect("a tree holding the key has a root"), key);
+
+        PersistentBst { root, version: self.version + 1 }
+    }
+
+    /**
+     * Walk down to where the key is or should be, then copy the path bottom-up
+     * The walk is a loop rather than a recursion, so a tree gone tall from sorted input does not overflow the stack
+     */
+    fn insert_below(root: Option<&PersistentLink<K, V>>, key: K, value: V) -> Option<PersistentLink<K, V>> {
+        //the nodes on the way down and whether the path went left of each one
+        let mut path = Vec::new();
+        let mut x_node = root;
+
+        let inserted = loop {
+            let Some(current) = x_node else {
+                break PersistentNode::new(key, value, None, None);
+            };
+
+            match key.cmp(&current.key) {
+                Ordering::Less => {
+                    debug!("- Copy the node {:?}, go left and share its right subtree", current.key);
+
+                    path.push((current, true));
+                    x_node = current.left.as_ref();
+                },
+                Ordering::Greater => {
+                    debug!("- Copy the node {:?}, go right and share its left subtree", current.key);
+
+                    path.push((current, false));
+                    x_node = current.right.as_ref();
+                },
+                Ordering::Equal => {
+                    debug!("- The node {:?} is already there, copy it with the new value and share both subtrees", current.key);
+
+                    break PersistentNode::new(key, value, current.left.clone(), current.right.clone());
+                }
+            }
+        };
+
+        PersistentBst::copy_path(path, Some(inserted))
+    }
+
+    //the key is known to be in the subtree of root
+    fn delete_below(root: &PersistentLink<K, V>, key: &K) -> Option<PersistentLink<K, V>> {
+        let mut path = Vec::new();
+        let mut x_node = root;
+
+        loop {
+            match key.cmp(&x_node.key) {
+                Ordering::Less => {
+                    path.push((x_node, true));
+                    x_node = x_node.left.as_ref().expect("the key is on the left");
+                },
+                Ordering::Greater => {
+                    path.push((x_node, false));
+                    x_node = x_node.right.as_ref().expect("the key is on the right");
+                },
+                Ordering::Equal => break,
+            }
+        }
+
+        let replacement = match (&x_node.left, &x_node.right) {
+            (None, child) | (child, None) => {
+                debug!("- The node {:?} has one child at most, the child takes its place as it is", x_node.key);
+
+                child.clone()
+            },
+            (Some(left), Some(right)) => {
+                let successor = PersistentNode::minimum(right);
+
+                debug!("- The node {:?} has both children, a copy of its successor {:?} takes its place", x_node.key, successor.key);
+
+                let right = PersistentBst::delete_minimum(right);
+                Some(PersistentNode::new(successor.key.clone(), successor.value.clone(), Some(left.clone()), right))
+            }
+        };
+
+        PersistentBst::copy_path(path, replacement)
+    }
+
+    //the subtree without its minimum, only the left spine down to it is copied
+    fn delete_minimum(root: &PersistentLink<K, V>) -> Option<PersistentLink<K, V>> {
+        let mut path = Vec::new();
+        let mut x_node = root;
+
+        while let Some(left) = &x_node.left {
+            path.push((x_node, true));
+            x_node = left;
+        }
+
+        PersistentBst::copy_path(path, x_node.right.clone())
+    }
+
+    //copy the path bottom-up, each copy takes the new subtree on the side the path went and shares the other side
+    fn copy_path(path: Vec<(&PersistentLink<K, V>, bool)>, subtree: Option<PersistentLink<K, V>>) -> Option<PersistentLink<K, V>> {
+        path.into_iter().rev().fold(subtree, |child, (x_node, went_left)| {
+            Some(if went_left {
+                PersistentNode::with_children(x_node, child, x_node.right.clone())
+            } else {
+                PersistentNode::with_children(x_node, x_node.left.clone(), child)
+            })
+        })
+    }
+}
+
+impl<K: Ord + Debug, V> PersistentBst<K, V> {
+    pub fn new() -> Self {
+        PersistentBst::default()
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn len(&self) -> usize {
+        PersistentNode::size_of(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn root(&self) -> Option<PersistentLink<K, V>> {
+        self.root.clone()
+    }
+
+    pub fn search(&self, key: &K) -> Option<PersistentLink<K, V>> {
+        self.path_to(key).pop().filter(|node| &node.key == key)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut x_node = self.root.as_ref();
+
+        while let Some(current) = x_node {
+            x_node = match key.cmp(&current.key) {
+                Ordering::Less => current.left.as_ref(),
+                Ordering::Greater => current.right.as_ref(),
+                Ordering::Equal => return Some(&current.value),
+            };
+        }
+
+        None
+    }
+
+    pub fn minimum(&self) -> Option<PersistentLink<K, V>> {
+        self.root.as_ref().map(PersistentNode::minimum)
+    }
+
+    pub fn maximum(&self) -> Option<PersistentLink<K, V>> {
+        self.root.as_ref().map(PersistentNode::maximum)
+    }
+
+    /**
+     * Find the successor of the node with the key, None if there's no such node or it is the maximum
+     * With no parent to walk up to, the path from the root stands in for it, the successor is the minimum of the
+     * right subtree, or else the lowest node on the path whose left subtree holds the key
+     */
+    pub fn successor(&self, key: &K) -> Option<PersistentLink<K, V>> {
+        let mut path = self.path_to(key);
+        let x_node = path.pop().filter(|node| &node.key == key)?;
+
+        if let Some(right) = &x_node.right {
+            return Some(PersistentNode::minimum(right));
+        }
+
+        path.into_iter().rev().find(|ancestor| key < &ancestor.key)
+    }
+
+    /**
+     * Find the predecessor of the node with the key, None if there's no such node or it is the minimum
+     */
+    pub fn predecessor(&self, key: &K) -> Option<PersistentLink<K, V>> {
+        let mut path = self.path_to(key);
+        let x_node = path.pop().filter(|node| &node.key == key)?;
+
+        if let Some(left) = &x_node.left {
+            return Some(PersistentNode::maximum(left));
+        }
+
+        path.into_iter().rev().find(|ancestor| key > &ancestor.key)
+    }
+
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        PersistentIter::new(self.root.as_ref(), self.len())
+    }
+
+    /**
+     * The number of nodes this version shares with the other one, e.g. to see how little an update cost
+     * A shared subtree is counted as a whole without walking into it
+     */
+    pub fn shared_with(&self, other: &PersistentBst<K, V>) -> usize {
+        let mut shared = 0;
+        let mut stack: Vec<&PersistentLink<K, V>> = self.root.iter().collect();
+
+        while let Some(x_node) = stack.pop() {
+            if other.holds(x_node) {
+                shared += x_node.size;
+                continue;
+            }
+
+            stack.extend(x_node.left.iter().chain(x_node.right.iter()));
+        }
+
+        shared
+    }
+
+    //the nodes from the root down to the one with the key, or to the last one before the key falls off the tree
+    fn path_to(&self, key: &K) -> Vec<PersistentLink<K, V>> {
+        let mut path = Vec::new();
+        let mut x_node = self.root.clone();
+
+        while let Some(current) = x_node {
+            x_node = match key.cmp(&current.key) {
+                Ordering::Less => current.left.clone(),
+                Ordering::Greater => current.right.clone(),
+                Ordering::Equal => None,
+            };
+            path.push(current);
+        }
+
+        path
+    }
+
+    //true if the very node is part of this version, the search path of its key is the only place it can be
+    fn holds(&self, node: &PersistentLink<K, V>) -> bool {
+        self.path_to(&node.key).last().is_some_and(|last| Rc::ptr_eq(last, node))
+    }
+}
+
+impl<'a, K: Ord + Debug, V> IntoIterator for &'a PersistentBst<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = PersistentIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/**
+ * In-order iterator over one version, the stack holds the left spine still to be visited
+ * It borrows the version, so the pairs come by reference without cloning
+ */
+pub struct PersistentIter<'a, K, V = ()> {
+    stack: Vec<&'a PersistentNode<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> PersistentIter<'a, K, V> {
+    fn new(root: Option<&'a PersistentLink<K, V>>, len: usize) -> Self {
+        let mut iter = PersistentIter { stack: Vec::new(), remaining: len };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut x_node: Option<&'a PersistentLink<K, V>>) {
+        while let Some(current) = x_node {
+            self.stack.push(current);
+            x_node = current.left.as_ref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for PersistentIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x_node = self.stack.pop()?;
+        self.remaining -= 1;
+        self.push_left_spine(x_node.right.as_ref());
+
+        Some((&x_node.key, &x_node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for PersistentIter<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::structure::bst::checks::TestRng;
+
+    fn check(tree: &PersistentBst<u32, u32>, model: &BTreeMap<u32, u32>) {
+        assert_eq!(tree.len(), model.len());
+        assert!(tree.iter().eq(model.iter()));
+    }
+
+    #[test]
+    fn old_versions_stay_as_they_were() {
+        let mut rng = TestRng::new(25);
+        let mut versions = vec![(PersistentBst::new(), BTreeMap::new())];
+
+        for step in 1..=600 {
+            let (tree, model) = versions.last().expect("the empty version is the first one");
+            let key = rng.next_u32() % 200;
+            let mut model = model.clone();
+
+            let tree = if rng.next_u32().is_multiple_of(3) {
+                model.remove(&key);
+                tree.delete(&key)
+            } else {
+                model.insert(key, step);
+                tree.insert_with_value(key, step)
+            };
+
+            versions.push((tree, model));
+
+            if step % 100 == 0 {
+                for (tree, model) in &versions {
+                    check(tree, model);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn an_update_copies_only_its_path() {
+        let tree: PersistentBst<u32, u32> = [50, 25, 75, 10, 30, 60, 90].into_iter().map(|key| (key, key)).collect();
+        let inserted = tree.insert_with_value(65, 65);
+        let deleted = inserted.delete(&25);
+
+        //the path down to 65 is 50, 75 and 60, the rest is shared
+        assert_eq!(inserted.shared_with(&tree), 4);
+        assert_eq!(inserted.version(), tree.version() + 1);
+        assert!(!tree.contains(&65));
+        assert!(inserted.contains(&25));
+        assert!(!deleted.contains(&25));
+        assert_eq!(tree.get(&25), Some(&25));
+    }
+
+    #[test]
+    fn successor_and_predecessor_follow_the_order() {
+        let mut rng = TestRng::new(250);
+        let mut tree = PersistentBst::new();
+        let mut model = BTreeMap::new();
+
+        for step in 0..300 {
+            let key = rng.next_u32() % 100;
+
+            if step % 4 == 3 {
+                tree = tree.delete(&key);
+                model.remove(&key);
+            } else {
+                tree = tree.insert_with_value(key, step);
+                model.insert(key, step);
+            }
+        }
+
+        check(&tree, &model);
+
+        for key in 0..100 {
+            let (successor, predecessor) = if model.contains_key(&key) {
+                (model.range(key + 1..).next(), model.range(..key).next_back())
+            } else {
+                (None, None)
+            };
+
+            assert_eq!(tree.successor(&key).map(|node| *node.key()), successor.map(|(key, _)| *key));
+            assert_eq!(tree.predecessor(&key).map(|node| *node.key()), predecessor.map(|(key, _)| *key));
+        }
+    }
+}
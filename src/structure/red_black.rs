@@ -0,0 +1,337 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::balance::{BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use crate::tool::DotStyle;
+
+/**
+ * The colour bit of a red-black node, a new node is red until the fixup says otherwise
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Colour {
+    #[default]
+    Red,
+    Black,
+}
+
+impl<K, V> NodeMeta<K, V> for Colour {}
+
+impl DotStyle for Colour {
+    fn dot_attributes(&self) -> Option<String> {
+        match self {
+            Colour::Red => Some("style=filled, fillcolor=red, fontcolor=white".to_string()),
+            Colour::Black => Some("style=filled, fillcolor=black, fontcolor=white".to_string()),
+        }
+    }
+}
+
+pub type RedBlackNodeLink<K, V = ()> = BstNodeLink<K, V, Colour>;
+
+/**
+ * Red-black policy according to the book, NIL leaves are black and are not stored, the meta of a node keeps its colour
+ * The root is black, a red node has black children and every path from a node down to NIL goes through
+ * the same number of black nodes
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedBlackBalance;
+
+/**
+ * Red-black tree, only insert and delete differ from the plain BST, the read API comes from BinarySearchTree through Deref
+ */
+pub type RedBlackTree<K, V = ()> = BalancedTree<K, V, RedBlackBalance>;
+
+impl<K: Ord + Debug, V> RedBlackTree<K, V> {
+    /**
+     * Number of black nodes on any path from the root down to NIL, the root included
+     */
+    pub fn black_height(&self) -> usize {
+        let mut black_height = 0;
+        let mut x_node = self.root();
+
+        while let Some(current) = x_node {
+            if current.borrow().meta == Colour::Black {
+                black_height += 1;
+            }
+            x_node = current.borrow().left.clone();
+        }
+
+        black_height
+    }
+}
+
+impl RedBlackBalance {
+    //NIL is black
+    fn colour_of<K, V>(node: &Option<RedBlackNodeLink<K, V>>) -> Colour {
+        node.as_ref().map_or(Colour::Black, |node| node.borrow().meta)
+    }
+
+    fn set_colour<K, V>(node: &RedBlackNodeLink<K, V>, colour: Colour) {
+        node.borrow_mut().meta = colour;
+    }
+
+    fn insert_fixup<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Colour>, mut z_node: RedBlackNodeLink<K, V>) {
+        debug!("- Fix up the red node {:?}", z_node.borrow().key);
+
+        loop {
+            let Some(parent) = BstNode::upgrade_weak_to_strong(z_node.borrow().parent.clone()) else {
+                break;
+            };
+
+            if parent.borrow().meta == Colour::Black {
+                debug!("- The parent {:?} of the node {:?} is black, nothing left to fix", parent.borrow().key, z_node.borrow().key);
+                break;
+            }
+
+            //a red parent is never the root, so the grandparent exists
+            let grandparent = BstNode::upgrade_weak_to_strong(parent.borrow().parent.clone()).expect("a red node has a parent");
+            let parent_is_left = BstNode::is_left_child(&grandparent, &parent);
+            let uncle = if parent_is_left { grandparent.borrow().right.clone() } else { grandparent.borrow().left.clone() };
+
+            if let Some(uncle) = uncle.filter(|uncle| uncle.borrow().meta == Colour::Red) {
+                debug!("- Case 1: the parent {:?} and the uncle {:?} are both red, recolour them black and the grandparent {:?} red", parent.borrow().key, uncle.borrow().key, grandparent.borrow().key);
+
+                RedBlackBalance::set_colour(&parent, Colour::Black);
+                RedBlackBalance::set_colour(&uncle, Colour::Black);
+                RedBlackBalance::set_colour(&grandparent, Colour::Red);
+
+                z_node = grandparent;
+                continue;
+            }
+
+            let mut parent = parent;
+
+            if BstNode::is_left_child(&parent, &z_node) != parent_is_left {
+                debug!("- Case 2: the node {:?} is an inner child, rotate at its parent {:?} to turn it into case 3", z_node.borrow().key, parent.borrow().key);
+
+                z_node = parent;
+                tree.rotate(&z_node, parent_is_left);
+                parent = BstNode::upgrade_weak_to_strong(z_node.borrow().parent.clone()).expect("the rotated node has a parent");
+            }
+
+            debug!("- Case 3: recolour the parent {:?} black and the grandparent {:?} red, then rotate at the grandparent", parent.borrow().key, grandparent.borrow().key);
+
+            RedBlackBalance::set_colour(&parent, Colour::Black);
+            RedBlackBalance::set_colour(&grandparent, Colour::Red);
+            tree.rotate(&grandparent, !parent_is_left);
+        }
+
+        if let Some(root) = tree.root() {
+            RedBlackBalance::set_colour(&root, Colour::Black);
+        }
+    }
+
+    /**
+     * The relinking is the one of tree_delete_node, it hands back the node x that took the emptied place and
+     * its parent, only the colour that left the tree has to be worked out around it
+     */
+    fn rb_delete<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Colour>, z_node: &RedBlackNodeLink<K, V>) {
+        debug!("- Delete the node {:?} of the colour {:?}", z_node.borrow().key, z_node.borrow().meta);
+
+        //a node with two children is replaced by its successor y, the colour of y is the one leaving its old place
+        let y_node = match (&z_node.borrow().left, &z_node.borrow().right) {
+            (Some(_), Some(right)) => Some(BstNode::minimum(right)),
+            _ => None,
+        };
+        let y_original_colour = y_node.as_ref().map_or(z_node.borrow().meta, |y_node| y_node.borrow().meta);
+
+        let (x_node, x_parent) = tree.delete_node(z_node);
+
+        if let Some(y_node) = y_node {
+            debug!("- The successor {:?} took the place of the node {:?}, give it the colour {:?}", y_node.borrow().key, z_node.borrow().key, z_node.borrow().meta);
+
+            y_node.borrow_mut().meta = z_node.borrow().meta;
+        }
+
+        if y_original_colour == Colour::Black {
+            RedBlackBalance::delete_fixup(tree, x_node, x_parent);
+        }
+    }
+
+    //x_node carries an extra black, push it up until it lands on a red node or the root
+    fn delete_fixup<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Colour>, mut x_node: Option<RedBlackNodeLink<K, V>>, mut x_parent: Option<RedBlackNodeLink<K, V>>) {
+        match &x_node {
+            Some(x_node) => debug!("- A black node is removed, the node {:?} carries an extra black", x_node.borrow().key),
+            None => debug!("- A black node is removed, NIL carries an extra black"),
+        }
+
+        while RedBlackBalance::colour_of(&x_node) == Colour::Black {
+            let Some(parent) = x_parent.clone() else {
+                break;
+            };
+
+            //a doubly black NIL always has a sibling, so the empty side is the side of x_node
+            let x_is_left = match &x_node {
+                Some(x_node) => BstNode::is_left_child(&parent, x_node),
+                None => parent.borrow().left.is_none(),
+            };
+            let sibling = |parent: &RedBlackNodeLink<K, V>| {
+                let sibling = if x_is_left { parent.borrow().right.clone() } else { parent.borrow().left.clone() };
+                sibling.expect("a doubly black node has a sibling")
+            };
+
+            let mut w_node = sibling(&parent);
+
+            if w_node.borrow().meta == Colour::Red {
+                debug!("- Case 1: the sibling {:?} is red, recolour it black and the parent {:?} red, then rotate at the parent", w_node.borrow().key, parent.borrow().key);
+
+                RedBlackBalance::set_colour(&w_node, Colour::Black);
+                RedBlackBalance::set_colour(&parent, Colour::Red);
+                tree.rotate(&parent, x_is_left);
+                w_node = sibling(&parent);
+            }
+
+            let (near, far) = if x_is_left {
+                (w_node.borrow().left.clone(), w_node.borrow().right.clone())
+            } else {
+                (w_node.borrow().right.clone(), w_node.borrow().left.clone())
+            };
+
+            if RedBlackBalance::colour_of(&near) == Colour::Black && RedBlackBalance::colour_of(&far) == Colour::Black {
+                debug!("- Case 2: both children of the sibling {:?} are black, recolour it red and move up to the parent {:?}", w_node.borrow().key, parent.borrow().key);
+
+                RedBlackBalance::set_colour(&w_node, Colour::Red);
+                x_parent = BstNode::upgrade_weak_to_strong(parent.borrow().parent.clone());
+                x_node = Some(parent);
+                continue;
+            }
+
+            if RedBlackBalance::colour_of(&far) == Colour::Black {
+                let near = near.expect("the near child is red");
+
+                debug!("- Case 3: the near child {:?} of the sibling {:?} is red, recolour and rotate at the sibling to turn it into case 4", near.borrow().key, w_node.borrow().key);
+
+                RedBlackBalance::set_colour(&near, Colour::Black);
+                RedBlackBalance::set_colour(&w_node, Colour::Red);
+                tree.rotate(&w_node, !x_is_left);
+                w_node = sibling(&parent);
+            }
+
+            debug!("- Case 4: the far child of the sibling {:?} is red, recolour and rotate at the parent {:?}, the extra black is gone", w_node.borrow().key, parent.borrow().key);
+
+            let far = if x_is_left { w_node.borrow().right.clone() } else { w_node.borrow().left.clone() };
+
+            RedBlackBalance::set_colour(&w_node, parent.borrow().meta);
+            RedBlackBalance::set_colour(&parent, Colour::Black);
+            if let Some(far) = far {
+                RedBlackBalance::set_colour(&far, Colour::Black);
+            }
+            tree.rotate(&parent, x_is_left);
+
+            x_node = tree.root();
+            x_parent = None;
+        }
+
+        if let Some(x_node) = x_node {
+            RedBlackBalance::set_colour(&x_node, Colour::Black);
+        }
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for RedBlackBalance {
+    type Meta = Colour;
+
+    const NAME: &'static str = "Red-black tree";
+
+    /**
+     * RB-INSERT, the node goes in as the plain BST puts it and starts out red, then RB-INSERT-FIXUP
+     * restores the properties from there up
+     */
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V, Colour>, node: &RedBlackNodeLink<K, V>) {
+        RedBlackBalance::insert_fixup(tree, node.clone());
+    }
+
+    /**
+     * RB-DELETE, the relinking is the one of the plain BST, which keeps track of the node x that moves
+     * into the place of the removed one, RB-DELETE-FIXUP starts from x if a black node was removed
+     */
+    fn delete_node(&mut self, tree: &mut BinarySearchTree<K, V, Colour>, node: &RedBlackNodeLink<K, V>) {
+        RedBlackBalance::rb_delete(tree, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::structure::bst::checks::{self, TestRng};
+
+    //check the colours below x_node, return the number of black nodes on every path down to NIL
+    fn check_colours(x_node: &Option<RedBlackNodeLink<u32>>) -> usize {
+        let Some(x_node) = x_node else {
+            return 0;
+        };
+
+        let node = x_node.borrow();
+
+        if node.meta == Colour::Red {
+            for child in node.left.iter().chain(node.right.iter()) {
+                assert_eq!(child.borrow().meta, Colour::Black, "the red node {:?} has a red child", node.key);
+            }
+        }
+
+        let left = check_colours(&node.left);
+        let right = check_colours(&node.right);
+
+        assert_eq!(left, right, "the paths below the node {:?} go through different numbers of black nodes", node.key);
+
+        left + usize::from(node.meta == Colour::Black)
+    }
+
+    fn check(tree: &RedBlackTree<u32>) {
+        if let Some(root) = tree.root() {
+            assert_eq!(root.borrow().meta, Colour::Black, "the root is red");
+        }
+
+        assert_eq!(check_colours(&tree.root()), tree.black_height());
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_colours() {
+        checks::random_inserts_and_deletes(&mut RedBlackTree::new(), 16, check);
+    }
+
+    #[test]
+    fn an_insert_rotates_twice_and_a_delete_three_times_at_most() {
+        let mut rng = TestRng::new(160);
+        let mut tree = RedBlackTree::new();
+
+        for _ in 0..3000 {
+            let key = rng.next_u32() % 500;
+            let rotations = tree.rotations();
+
+            if rng.next_u32().is_multiple_of(3) {
+                tree.delete(&key);
+                assert!(tree.rotations() - rotations <= 3);
+            } else {
+                tree.insert(key);
+                assert!(tree.rotations() - rotations <= 2);
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_input_stays_within_twice_the_black_height() {
+        let mut tree = RedBlackTree::new();
+        let keys: BTreeSet<u32> = (0..1000).collect();
+
+        for &key in &keys {
+            tree.insert(key);
+        }
+
+        checks::check_links(&tree, &keys);
+        check(&tree);
+        assert!(tree.height() <= 2 * tree.black_height());
+        assert!(tree.rotations() > 0);
+
+        for key in (0..1000).step_by(2) {
+            tree.delete(&key);
+        }
+
+        checks::check_links(&tree, &(1..1000).step_by(2).collect());
+        check(&tree);
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::balance::{AlphaError, BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicatePolicy};
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+const DEFAULT_ALPHA: f64 = 0.7;
+
+/**
+ * Scapegoat policy, no balancing data is kept per node, the subtree sizes the BST keeps anyway are enough
+ * A node inserted deeper than log base 1/alpha of the node count has an ancestor whose child holds more than
+ * alpha of its nodes, that scapegoat's subtree is rebuilt into perfect balance
+ * After deletions bring the node count below alpha of the most it has been, the whole tree is rebuilt
+ * The closer alpha is to 0.5 the more balanced the tree and the more often it's rebuilt
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct ScapegoatBalance {
+    alpha: f64,
+    max_len: usize,
+    rebuilds: usize,
+}
+
+impl Default for ScapegoatBalance {
+    fn default() -> Self {
+        ScapegoatBalance { alpha: DEFAULT_ALPHA, max_len: 0, rebuilds: 0 }
+    }
+}
+
+/**
+ * Scapegoat tree, only insert and delete differ from the plain BST, the read API comes from BinarySearchTree through Deref
+ */
+pub type ScapegoatTree<K, V = ()> = BalancedTree<K, V, ScapegoatBalance>;
+
+impl<K: Ord + Debug, V> ScapegoatTree<K, V> {
+    /**
+     * The alpha must lie strictly between 0.5 and 1, any other one is an AlphaError
+     */
+    pub fn with_alpha(alpha: f64) -> Result<Self, AlphaError> {
+        ScapegoatTree::with_policy_and_alpha(DuplicatePolicy::default(), alpha)
+    }
+
+    pub fn with_policy_and_alpha(policy: DuplicatePolicy, alpha: f64) -> Result<Self, AlphaError> {
+        Ok(ScapegoatTree::with_balance(policy, ScapegoatBalance::with_alpha(alpha)?))
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.balance().alpha
+    }
+
+    /**
+     * Number of subtrees rebuilt so far, the full rebuilds after deletions included
+     */
+    pub fn rebuilds(&self) -> usize {
+        self.balance().rebuilds
+    }
+}
+
+impl ScapegoatBalance {
+    pub fn with_alpha(alpha: f64) -> Result<Self, AlphaError> {
+        if !(alpha > 0.5 && alpha < 1.0) {
+            return Err(AlphaError { alpha, expected: "strictly between 0.5 and 1" });
+        }
+
+        Ok(ScapegoatBalance { alpha, ..ScapegoatBalance::default() })
+    }
+
+    //the deepest a node may be, log base 1/alpha of the node count
+    fn depth_bound(&self, len: usize) -> usize {
+        ((len as f64).ln() / (1.0 / self.alpha).ln()).floor() as usize
+    }
+
+    //number of edges from the root down to x_node
+    fn depth_of<K: Ord + Debug, V>(x_node: &BstNodeLink<K, V>) -> usize {
+        let mut depth = 0;
+        let mut parent = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
+
+        while let Some(current) = parent {
+            depth += 1;
+            parent = BstNode::upgrade_weak_to_strong(current.borrow().parent.clone());
+        }
+
+        depth
+    }
+
+    //the lowest ancestor of x_node with a child holding more than alpha of its nodes, the root if there's none
+    fn find_scapegoat<K: Ord + Debug, V>(&self, x_node: &BstNodeLink<K, V>) -> BstNodeLink<K, V> {
+        let mut child = x_node.clone();
+
+        loop {
+            let Some(parent) = BstNode::upgrade_weak_to_strong(child.borrow().parent.clone()) else {
+                return child;
+            };
+
+            let child_size = child.borrow().size;
+            let parent_size = parent.borrow().size;
+
+            if child_size as f64 > self.alpha * parent_size as f64 {
+                debug!("- The node {:?} is the scapegoat, its child {:?} holds {} of its {} nodes", parent.borrow().key, child.borrow().key, child_size, parent_size);
+
+                return parent;
+            }
+
+            child = parent;
+        }
+    }
+
+    //relink the subtree of x_node into perfect balance, the nodes stay the same so handles to them stay valid
+    fn rebuild<K: Ord + Debug, V>(&mut self, tree: &mut BinarySearchTree<K, V>, x_node: &BstNodeLink<K, V>) {
+        let parent = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
+        let is_left = parent.as_ref().is_some_and(|parent| BstNode::is_left_child(parent, x_node));
+
+        let nodes = ScapegoatBalance::flatten(x_node);
+
+        debug!("- Rebuild the subtree of {} nodes below the node {:?} into perfect balance", nodes.len(), x_node.borrow().key);
+
+        let top = ScapegoatBalance::build(&nodes);
+
+        if let Some(ref top) = top {
+            top.borrow_mut().parent = parent.as_ref().map(BstNode::downgrade);
+
+            debug!("- The node {:?} is the top of the rebuilt subtree", top.borrow().key);
+        }
+
+        match parent {
+            None => *tree.root_mut() = top,
+            Some(parent) if is_left => parent.borrow_mut().left = top,
+            Some(parent) => parent.borrow_mut().right = top,
+        }
+
+        self.rebuilds += 1;
+    }
+
+    //the nodes of the subtree of x_node in order
+    fn flatten<K: Ord + Debug, V>(x_node: &BstNodeLink<K, V>) -> Vec<BstNodeLink<K, V>> {
+        let mut nodes = Vec::with_capacity(x_node.borrow().size);
+        let mut stack = Vec::new();
+        let mut current = Some(x_node.clone());
+
+        while current.is_some() || !stack.is_empty() {
+            while let Some(node) = current {
+                current = node.borrow().left.clone();
+                stack.push(node);
+            }
+
+            let node = stack.pop().expect("the stack is not empty");
+            current = node.borrow().right.clone();
+            nodes.push(node);
+        }
+
+        nodes
+    }
+
+    //hang the middle node on top and build both halves below it, return the top
+    fn build<K: Ord + Debug, V>(nodes: &[BstNodeLink<K, V>]) -> Option<BstNodeLink<K, V>> {
+        if nodes.is_empty() {
+            return None;
+        }
+
+        let middle = nodes.len() / 2;
+        let top = nodes[middle].clone();
+
+        let left = ScapegoatBalance::build(&nodes[..middle]);
+        let right = ScapegoatBalance::build(&nodes[middle + 1..]);
+
+        for child in [&left, &right].into_iter().flatten() {
+            child.borrow_mut().parent = Some(BstNode::downgrade(&top));
+        }
+
+        let mut top_node = top.borrow_mut();
+        top_node.left = left;
+        top_node.right = right;
+        top_node.resize();
+        drop(top_node);
+
+        Some(top)
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for ScapegoatBalance {
+    type Meta = ();
+
+    const NAME: &'static str = "Scapegoat tree";
+
+    //if the new node ended up too deep rebuild the subtree of its scapegoat
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V>, node: &BstNodeLink<K, V>) {
+        self.max_len = self.max_len.max(tree.len());
+
+        let depth = ScapegoatBalance::depth_of(node);
+        let bound = self.depth_bound(tree.len());
+
+        if depth > bound {
+            debug!("- The node {:?} is at the depth of {}, deeper than the bound of {}", node.borrow().key, depth, bound);
+
+            let scapegoat = self.find_scapegoat(node);
+            self.rebuild(tree, &scapegoat);
+        }
+    }
+
+    //rebuild the whole tree once it shrank below alpha of the most nodes it had
+    fn after_delete(&mut self, tree: &mut BinarySearchTree<K, V>, _lowest: Option<BstNodeLink<K, V>>) {
+        if (tree.len() as f64) < self.alpha * self.max_len as f64 {
+            debug!("- The tree shrank to {} nodes from {}, rebuild the whole tree", tree.len(), self.max_len);
+
+            if let Some(root) = tree.root() {
+                self.rebuild(tree, &root);
+            }
+
+            self.max_len = tree.len();
+        }
+    }
+
+    fn stats(&self, _tree: &BinarySearchTree<K, V>) -> Option<String> {
+        Some(format!("{} rebuilds so far with alpha of {}", self.rebuilds, self.alpha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::structure::bst::checks;
+
+    //a node inserted too deep gets its scapegoat rebuilt and deletes never push a node down,
+    //so no path is longer than the bound for the most nodes the tree had since the last full rebuild
+    fn check(tree: &ScapegoatTree<u32>) {
+        let balance = tree.balance();
+
+        assert!(tree.height() <= balance.depth_bound(balance.max_len) + 1, "the height of {} is over the bound for {} nodes", tree.height(), balance.max_len);
+        assert!(tree.len() as f64 >= balance.alpha * balance.max_len as f64);
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_height_bound() {
+        checks::random_inserts_and_deletes(&mut ScapegoatTree::new(), 19, check);
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_height_bound_for_a_small_alpha() {
+        checks::random_inserts_and_deletes(&mut ScapegoatTree::with_alpha(0.55).expect("0.55 lies between 0.5 and 1"), 190, check);
+    }
+
+    #[test]
+    fn an_alpha_out_of_range_is_refused() {
+        for alpha in [0.5, 1.0, 0.0, f64::NAN] {
+            assert!(ScapegoatTree::<u32>::with_alpha(alpha).is_err());
+        }
+
+        let error = ScapegoatBalance::with_alpha(1.5).expect_err("1.5 is over 1");
+        assert_eq!(error.to_string(), "alpha must lie strictly between 0.5 and 1, got 1.5");
+    }
+
+    #[test]
+    fn sorted_input_is_rebuilt() {
+        let mut tree = ScapegoatTree::new();
+
+        for key in 0..1000 {
+            tree.insert(key);
+            check(&tree);
+        }
+
+        checks::check_links(&tree, &(0..1000).collect());
+        assert!(tree.rebuilds() > 0);
+    }
+
+    #[test]
+    fn deletes_rebuild_the_whole_tree() {
+        let mut tree = ScapegoatTree::new();
+        let mut keys: BTreeSet<u32> = (0..100).collect();
+
+        for &key in &keys {
+            tree.insert(key);
+        }
+
+        let rebuilds = tree.rebuilds();
+
+        //the tree is rebuilt once it's down to 69 of the 100 nodes, below the alpha of 0.7
+        for key in 0..31 {
+            tree.delete(&key);
+            keys.remove(&key);
+
+            assert_eq!(tree.rebuilds(), rebuilds + usize::from(key == 30));
+        }
+
+        checks::check_links(&tree, &keys);
+        check(&tree);
+
+        //a rebuilt tree is perfectly balanced, 69 nodes take 7 levels
+        assert_eq!(tree.height(), 7);
+        assert_eq!(tree.balance().max_len, 69);
+    }
+}
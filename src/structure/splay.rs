@@ -0,0 +1,275 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::balance::{BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::BinarySearchTree;
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+/**
+ * Splay policy, every search, insert and delete splays the node it reaches up to the root so keys that are
+ * accessed often stay close to the root, no balancing data is kept per node
+ * A single access may take O(n) rotations, but any sequence of m accesses takes O(m log n) rotations in total,
+ * the access counter along with the rotation count of the tree keeps track of how the amortised cost works out
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SplayBalance {
+    accesses: usize,
+}
+
+/**
+ * Splay tree, the read API comes from BinarySearchTree through Deref except for search, which splays
+ */
+pub type SplayTree<K, V = ()> = BalancedTree<K, V, SplayBalance>;
+
+impl<K: Ord + Debug, V> SplayTree<K, V> {
+    /**
+     * Search for the key and splay the node found, if the key is missing the last node on the path is splayed
+     * Unlike the search of the plain BST this one changes the shape, so it needs the tree mutably
+     */
+    pub fn search(&mut self, key: &K) -> Option<BstNodeLink<K, V>> {
+        self.access(key)
+    }
+
+    /**
+     * Number of splays done so far, one per search, insert or delete that reached a node
+     */
+    pub fn accesses(&self) -> usize {
+        self.balance().accesses
+    }
+
+    /**
+     * Rotations per access, this stays within O(log n) however skewed the accesses are
+     */
+    pub fn amortised_cost(&self) -> f64 {
+        self.balance().amortised_cost(self.rotations())
+    }
+}
+
+impl SplayBalance {
+    fn amortised_cost(&self, rotations: usize) -> f64 {
+        if self.accesses == 0 {
+            return 0.0;
+        }
+
+        rotations as f64 / self.accesses as f64
+    }
+
+    fn splay_to_root<K: Ord + Debug, V>(&mut self, tree: &mut BinarySearchTree<K, V>, x_node: &BstNodeLink<K, V>) {
+        self.accesses += 1;
+
+        let rotations = SplayBalance::splay(tree.root_mut(), x_node);
+        tree.add_rotations(rotations);
+    }
+
+    //rotate x_node up until it's the root, return the number of rotations
+    fn splay<K: Ord + Debug, V>(root: &mut Option<BstNodeLink<K, V>>, x_node: &BstNodeLink<K, V>) -> usize {
+        debug!("- Splay the node {:?} to the root", x_node.borrow().key);
+
+        let mut rotations = 0;
+
+        loop {
+            let Some(parent) = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone()) else {
+                break;
+            };
+
+            let x_is_left = BstNode::is_left_child(&parent, x_node);
+            let side = if x_is_left { "left" } else { "right" };
+
+            let grandparent = BstNode::upgrade_weak_to_strong(parent.borrow().parent.clone());
+
+            match grandparent {
+                None => {
+                    debug!("- Zig: the node {:?} is the {} child of the root {:?}, rotate at the root", x_node.borrow().key, side, parent.borrow().key);
+
+                    BstNode::rotate(root, &parent, !x_is_left);
+                    rotations += 1;
+                },
+                Some(grandparent) => {
+                    let parent_is_left = BstNode::is_left_child(&grandparent, &parent);
+
+                    if x_is_left == parent_is_left {
+                        debug!("- Zig-zig: the node {:?} and its parent {:?} are both {} children, rotate at the grandparent {:?} and then at the parent", x_node.borrow().key, parent.borrow().key, side, grandparent.borrow().key);
+
+                        BstNode::rotate(root, &grandparent, !parent_is_left);
+                        BstNode::rotate(root, &parent, !x_is_left);
+                    } else {
+                        debug!("- Zig-zag: the node {:?} is the {} child of its parent {:?}, rotate at the parent and then at the grandparent {:?}", x_node.borrow().key, side, parent.borrow().key, grandparent.borrow().key);
+
+                        BstNode::rotate(root, &parent, !x_is_left);
+                        BstNode::rotate(root, &grandparent, !parent_is_left);
+                    }
+
+                    rotations += 2;
+                }
+            }
+        }
+
+        debug!("- The node {:?} is the root after {} rotations", x_node.borrow().key, rotations);
+
+        rotations
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for SplayBalance {
+    type Meta = ();
+
+    const NAME: &'static str = "Splay tree";
+
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V>, node: &BstNodeLink<K, V>) {
+        self.splay_to_root(tree, node);
+    }
+
+    /**
+     * The node is at the root already, the delete reached it as an access, take it out and join both subtrees
+     * The maximum of the left subtree is splayed to its top so the right subtree can hang off its empty right slot
+     */
+    fn delete_node(&mut self, tree: &mut BinarySearchTree<K, V>, node: &BstNodeLink<K, V>) {
+        debug!("- Delete the node {:?} at the root and join its subtrees", node.borrow().key);
+
+        let left = node.borrow_mut().left.take();
+        let right = node.borrow_mut().right.take();
+        node.borrow_mut().size = 1;
+
+        let joined = match left {
+            None => {
+                debug!("- The node {:?} does not have a left subtree, its right subtree becomes the tree", node.borrow().key);
+
+                if let Some(ref right) = right {
+                    right.borrow_mut().parent = None;
+                }
+
+                right
+            },
+            Some(left) => {
+                left.borrow_mut().parent = None;
+
+                let maximum = BstNode::maximum(&left);
+
+                debug!("- Splay the maximum {:?} of the left subtree to its top", maximum.borrow().key);
+
+                let mut left_root = Some(left);
+                let rotations = SplayBalance::splay(&mut left_root, &maximum);
+                tree.add_rotations(rotations);
+
+                debug!("- Hang the right subtree below the node {:?}", maximum.borrow().key);
+
+                if let Some(ref right) = right {
+                    right.borrow_mut().parent = Some(BstNode::downgrade(&maximum));
+                }
+                maximum.borrow_mut().right = right;
+                BstNode::resize_upward(Some(maximum.clone()));
+
+                Some(maximum)
+            }
+        };
+
+        *tree.root_mut() = joined;
+        tree.shrink();
+    }
+
+    fn on_access(&mut self, tree: &mut BinarySearchTree<K, V>, node: &BstNodeLink<K, V>) {
+        self.splay_to_root(tree, node);
+    }
+
+    fn stats(&self, tree: &BinarySearchTree<K, V>) -> Option<String> {
+        Some(format!("{} splays took {} rotations, {:.2} rotations per access", self.accesses, tree.rotations(), self.amortised_cost(tree.rotations())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::structure::bst::checks::{self, TestRng};
+
+    fn root_key(tree: &SplayTree<u32>) -> Option<u32> {
+        tree.root().and_then(|root| root.borrow().key)
+    }
+
+    #[test]
+    fn the_node_reached_is_splayed_to_the_root() {
+        let mut rng = TestRng::new(17);
+        let mut tree = SplayTree::new();
+        let mut keys = BTreeSet::new();
+
+        for _ in 0..3000 {
+            let key = rng.next_u32() % 300;
+
+            match rng.next_u32() % 3 {
+                0 => {
+                    assert_eq!(tree.insert(key), keys.insert(key));
+                    assert_eq!(root_key(&tree), Some(key));
+                },
+                1 => {
+                    let found = tree.search(&key);
+                    assert_eq!(found.is_some(), keys.contains(&key));
+
+                    //a missing key splays the last node on its path, which is next to it in the order
+                    let root = root_key(&tree);
+                    if found.is_some() {
+                        assert_eq!(root, Some(key));
+                    } else if !keys.is_empty() {
+                        let before = keys.range(..key).next_back().copied();
+                        let after = keys.range(key..).next().copied();
+                        assert!(root == before || root == after, "the root {:?} is not next to the missing key {}", root, key);
+                    }
+                },
+                _ => {
+                    let smaller = keys.range(..key).next_back().copied();
+                    let existed = keys.remove(&key);
+                    assert_eq!(tree.delete(&key).is_some(), existed);
+
+                    //the maximum of the left subtree takes the root, a missing key leaves its neighbour there
+                    if existed && smaller.is_some() {
+                        assert_eq!(root_key(&tree), smaller);
+                    }
+                }
+            }
+
+            checks::check_links(&tree, &keys);
+        }
+    }
+
+    #[test]
+    fn delete_joins_the_subtrees() {
+        let mut tree = SplayTree::new();
+        let mut keys: BTreeSet<u32> = (0..100).collect();
+
+        for &key in &keys {
+            tree.insert(key);
+        }
+
+        //the sorted input leaves a path down the left, every delete joins a deep left subtree
+        for key in (0..100).step_by(3) {
+            assert!(tree.delete(&key).is_some());
+            keys.remove(&key);
+
+            checks::check_links(&tree, &keys);
+        }
+
+        for key in keys.clone() {
+            assert!(tree.delete(&key).is_some());
+            keys.remove(&key);
+
+            checks::check_links(&tree, &keys);
+        }
+
+        assert!(tree.is_empty());
+        assert!(tree.root().is_none());
+    }
+
+    #[test]
+    fn a_missing_key_is_not_deleted() {
+        let mut tree = SplayTree::new();
+
+        for key in [10, 20, 30] {
+            tree.insert(key);
+        }
+
+        assert!(tree.delete(&25).is_none());
+        assert_eq!(tree.len(), 3);
+        checks::check_links(&tree, &[10, 20, 30].into_iter().collect());
+    }
+}
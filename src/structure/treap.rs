@@ -0,0 +1,381 @@
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+
+use log::debug;
+
+use crate::structure::balance::{BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicatePolicy};
+use crate::structure::bst::{BstNode, BstNodeLink, NodeMeta};
+use crate::tool::DotStyle;
+
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/**
+ * Xorshift generator, small and good enough to draw priorities
+ * The same seed always draws the same priorities, so a run can be reproduced
+ */
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    //xorshift never leaves the state of 0, so that seed is swapped for the default one
+    pub fn new(seed: u64) -> Self {
+        XorShift { state: if seed == 0 { DEFAULT_SEED } else { seed } }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+}
+
+impl Default for XorShift {
+    fn default() -> Self {
+        XorShift::new(DEFAULT_SEED)
+    }
+}
+
+/**
+ * Priority of a treap node, a parent never has a lower priority than its children
+ * A node gets its random priority once it's in the tree, until then it has the priority of 0
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Priority(pub u32);
+
+impl<K, V> NodeMeta<K, V> for Priority {}
+
+impl DotStyle for Priority {
+    fn dot_label(&self) -> Option<String> {
+        Some(format!("p={}", self.0))
+    }
+}
+
+pub type TreapNodeLink<K, V = ()> = BstNodeLink<K, V, Priority>;
+
+//the roots of both halves of a split subtree, either may be empty
+type SplitRoots<K, V> = (Option<TreapNodeLink<K, V>>, Option<TreapNodeLink<K, V>>);
+
+/**
+ * Treap policy, a BST by key and a max-heap by random priority at the same time, which keeps the expected height
+ * logarithmic whatever order the keys come in, the meta of a node keeps its priority
+ */
+#[derive(Debug, Clone, Default)]
+pub struct TreapBalance {
+    rng: XorShift,
+}
+
+impl TreapBalance {
+    pub fn with_seed(seed: u64) -> Self {
+        TreapBalance { rng: XorShift::new(seed) }
+    }
+}
+
+/**
+ * Returned by merge when the keys of both treaps overlap, both treaps are handed back as they were
+ */
+#[derive(Debug)]
+pub struct MergeError<K: Ord + Debug, V = ()> {
+    pub left: Treap<K, V>,
+    pub right: Treap<K, V>,
+}
+
+impl<K: Ord + Debug, V> Display for MergeError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the keys of the left treap must come before the keys of the right treap")
+    }
+}
+
+impl<K: Ord + Debug, V: Debug> Error for MergeError<K, V> {}
+
+/**
+ * Treap, only insert, delete, split and merge differ from the plain BST, the read API comes from BinarySearchTree through Deref
+ */
+pub type Treap<K, V = ()> = BalancedTree<K, V, TreapBalance>;
+
+impl<K: Ord + Debug, V> Treap<K, V> {
+    pub fn with_seed(seed: u64) -> Self {
+        Treap::with_balance(DuplicatePolicy::default(), TreapBalance::with_seed(seed))
+    }
+
+    pub fn with_policy_and_seed(policy: DuplicatePolicy, seed: u64) -> Self {
+        Treap::with_balance(policy, TreapBalance::with_seed(seed))
+    }
+
+    /**
+     * Split the treap into the nodes with keys smaller than key and the rest, the nodes are relinked
+     * in place along a single path, so it takes O(h)
+     */
+    pub fn split(self, key: &K) -> (Treap<K, V>, Treap<K, V>) {
+        debug!("- Split the treap at the key {:?}", key);
+
+        let (mut tree, mut balance) = self.into_parts();
+        let (smaller, rest) = TreapBalance::split_subtree(tree.root_mut().take(), key);
+
+        for root in [&smaller, &rest].into_iter().flatten() {
+            root.borrow_mut().parent = None;
+        }
+
+        let policy = tree.policy();
+        let rest_balance = TreapBalance::with_seed(balance.rng.next_u64());
+
+        (
+            Treap::from_parts(BinarySearchTree::from_root(smaller, policy), balance),
+            Treap::from_parts(BinarySearchTree::from_root(rest, policy), rest_balance),
+        )
+    }
+
+    /**
+     * Join two treaps where every key of left is smaller than every key of right, the result keeps
+     * the duplicate policy and the generator of left, it takes O(h)
+     * Equal keys on both sides are only allowed under the Multiset policy, overlapping keys are a MergeError
+     */
+    pub fn merge(left: Treap<K, V>, right: Treap<K, V>) -> Result<Treap<K, V>, MergeError<K, V>> {
+        if let (Some(maximum), Some(minimum)) = (left.maximum(), right.minimum()) {
+            let ordered = match left.policy() {
+                DuplicatePolicy::Multiset => maximum.borrow().key <= minimum.borrow().key,
+                _ => maximum.borrow().key < minimum.borrow().key,
+            };
+
+            if !ordered {
+                debug!("- The maximum {:?} of the left treap does not come before the minimum {:?} of the right one", maximum.borrow().key, minimum.borrow().key);
+
+                return Err(MergeError { left, right });
+            }
+        }
+
+        debug!("- Merge the treap of {} nodes with the treap of {} nodes", left.len(), right.len());
+
+        let (mut left_tree, balance) = left.into_parts();
+        let (mut right_tree, _) = right.into_parts();
+        let merged = TreapBalance::merge_subtree(left_tree.root_mut().take(), right_tree.root_mut().take());
+
+        if let Some(ref merged) = merged {
+            merged.borrow_mut().parent = None;
+        }
+
+        Ok(Treap::from_parts(BinarySearchTree::from_root(merged, left_tree.policy()), balance))
+    }
+}
+
+impl TreapBalance {
+    fn sift_up<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Priority>, x_node: &TreapNodeLink<K, V>) {
+        loop {
+            let Some(parent) = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone()) else {
+                break;
+            };
+
+            if parent.borrow().meta >= x_node.borrow().meta {
+                break;
+            }
+
+            debug!("- The node {:?} outranks its parent {:?}, rotate it up", x_node.borrow().key, parent.borrow().key);
+
+            tree.rotate(&parent, !BstNode::is_left_child(&parent, x_node));
+        }
+    }
+
+    fn sift_down<K: Ord + Debug, V>(tree: &mut BinarySearchTree<K, V, Priority>, x_node: &TreapNodeLink<K, V>) {
+        loop {
+            let left = x_node.borrow().left.clone();
+            let right = x_node.borrow().right.clone();
+
+            let (Some(left), Some(right)) = (left, right) else {
+                break;
+            };
+
+            let left_up = left.borrow().meta > right.borrow().meta;
+            let child = if left_up { &left } else { &right };
+
+            debug!("- The node {:?} has two children, rotate its child {:?} of the higher priority up", x_node.borrow().key, child.borrow().key);
+
+            tree.rotate(x_node, !left_up);
+        }
+
+        debug!("- The node {:?} has one child at most, delete it in place", x_node.borrow().key);
+    }
+
+    //hang child in the slot of x_node and refresh its size, the children of x_node are up to date already
+    fn set_child<K: Ord + Debug, V>(x_node: &TreapNodeLink<K, V>, child: Option<TreapNodeLink<K, V>>, is_left: bool) {
+        if let Some(ref child) = child {
+            child.borrow_mut().parent = Some(BstNode::downgrade(x_node));
+        }
+
+        let mut x_node = x_node.borrow_mut();
+
+        if is_left {
+            x_node.left = child;
+        } else {
+            x_node.right = child;
+        }
+
+        x_node.resize();
+    }
+
+    fn split_subtree<K: Ord + Debug, V>(node: Option<TreapNodeLink<K, V>>, key: &K) -> SplitRoots<K, V> {
+        let Some(node) = node else {
+            return (None, None);
+        };
+
+        let smaller = node.borrow().key.as_ref().is_some_and(|node_key| node_key < key);
+
+        if smaller {
+            debug!("- The node {:?} goes to the left, split its right subtree", node.borrow().key);
+
+            let right = node.borrow_mut().right.take();
+            let (smaller, rest) = TreapBalance::split_subtree(right, key);
+            TreapBalance::set_child(&node, smaller, false);
+
+            (Some(node), rest)
+        } else {
+            debug!("- The node {:?} goes to the right, split its left subtree", node.borrow().key);
+
+            let left = node.borrow_mut().left.take();
+            let (smaller, rest) = TreapBalance::split_subtree(left, key);
+            TreapBalance::set_child(&node, rest, true);
+
+            (smaller, Some(node))
+        }
+    }
+
+    fn merge_subtree<K: Ord + Debug, V>(left: Option<TreapNodeLink<K, V>>, right: Option<TreapNodeLink<K, V>>) -> Option<TreapNodeLink<K, V>> {
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(left), Some(right)) => {
+                if left.borrow().meta >= right.borrow().meta {
+                    debug!("- The node {:?} outranks the node {:?}, it stays on top and takes the merge as its right subtree", left.borrow().key, right.borrow().key);
+
+                    let left_right = left.borrow_mut().right.take();
+                    let merged = TreapBalance::merge_subtree(left_right, Some(right));
+                    TreapBalance::set_child(&left, merged, false);
+
+                    Some(left)
+                } else {
+                    debug!("- The node {:?} outranks the node {:?}, it stays on top and takes the merge as its left subtree", right.borrow().key, left.borrow().key);
+
+                    let right_left = right.borrow_mut().left.take();
+                    let merged = TreapBalance::merge_subtree(Some(left), right_left);
+                    TreapBalance::set_child(&right, merged, true);
+
+                    Some(right)
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for TreapBalance {
+    type Meta = Priority;
+
+    const NAME: &'static str = "Treap";
+
+    //draw a priority for the new node and rotate it up while it outranks its parent
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V, Priority>, node: &TreapNodeLink<K, V>) {
+        let priority = Priority(self.rng.next_u32());
+        node.borrow_mut().meta = priority;
+
+        debug!("- The node {:?} draws the priority of {}", node.borrow().key, priority.0);
+
+        TreapBalance::sift_up(tree, node);
+    }
+
+    //rotate the node down below its child of the higher priority until it has one child at most, then delete it in place
+    fn delete_node(&mut self, tree: &mut BinarySearchTree<K, V, Priority>, node: &TreapNodeLink<K, V>) {
+        TreapBalance::sift_down(tree, node);
+        tree.delete_node(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::structure::bst::checks;
+
+    //no child outranks its parent below x_node
+    fn check_heap(x_node: &Option<TreapNodeLink<u32>>) {
+        let Some(x_node) = x_node else {
+            return;
+        };
+
+        let node = x_node.borrow();
+
+        for child in [&node.left, &node.right] {
+            if let Some(child) = child {
+                assert!(child.borrow().meta <= node.meta, "the node {:?} is outranked by its child {:?}", node.key, child.borrow().key);
+            }
+
+            check_heap(child);
+        }
+    }
+
+    fn check(tree: &Treap<u32>) {
+        check_heap(&tree.root());
+    }
+
+    fn treap_of(keys: &BTreeSet<u32>, seed: u64) -> Treap<u32> {
+        let mut tree = Treap::with_seed(seed);
+
+        //every other key first, so the input is not sorted
+        for &key in keys.iter().step_by(2).chain(keys.iter().skip(1).step_by(2)) {
+            tree.insert(key);
+        }
+
+        tree
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_heap_order() {
+        checks::random_inserts_and_deletes(&mut Treap::with_seed(18), 18, check);
+    }
+
+    #[test]
+    fn split_and_merge_round_trip() {
+        let keys: BTreeSet<u32> = (0..500).map(|key| key * 2).collect();
+
+        for at in [0, 1, 250, 501, 998, 1000] {
+            let (smaller, rest) = treap_of(&keys, 180).split(&at);
+
+            checks::check_links(&smaller, &keys.range(..at).copied().collect());
+            checks::check_links(&rest, &keys.range(at..).copied().collect());
+            check(&smaller);
+            check(&rest);
+
+            let merged = Treap::merge(smaller, rest).expect("the halves of a split are in order");
+
+            checks::check_links(&merged, &keys);
+            check(&merged);
+        }
+    }
+
+    #[test]
+    fn merge_refuses_overlapping_keys() {
+        let left = treap_of(&(0..50).collect(), 181);
+        let right = treap_of(&(40..100).collect(), 182);
+
+        let error = Treap::merge(left, right).expect_err("the keys of both treaps overlap");
+
+        checks::check_links(&error.left, &(0..50).collect());
+        checks::check_links(&error.right, &(40..100).collect());
+
+        //a key on both sides is an overlap too unless the policy is Multiset
+        let left = treap_of(&(0..50).collect(), 183);
+        let right = treap_of(&(49..100).collect(), 184);
+        assert!(Treap::merge(left, right).is_err());
+
+        let mut left = Treap::with_policy_and_seed(DuplicatePolicy::Multiset, 185);
+        let mut right = Treap::with_policy_and_seed(DuplicatePolicy::Multiset, 186);
+        left.insert(7);
+        right.insert(7);
+        assert_eq!(Treap::merge(left, right).expect("equal keys are allowed in a multiset").len(), 2);
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::Debug;
+
+use log::debug;
+
+use crate::structure::balance::{AlphaError, BalancePolicy, BalancedTree};
+use crate::structure::binary_search_tree::{BinarySearchTree, DuplicatePolicy};
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+const DEFAULT_ALPHA: f64 = 0.25;
+
+/**
+ * Weight-balanced policy, also known as BB[alpha], the weight of a node is the size of its subtree plus one
+ * Both subtrees of every node weigh at least alpha of the node, the subtree sizes the BST keeps anyway are
+ * all it needs, so no balancing data is kept per node
+ * A node out of balance is fixed by a single rotation, or a double one if the inner grandchild is too heavy
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct WeightBalance {
+    alpha: f64,
+}
+
+impl Default for WeightBalance {
+    fn default() -> Self {
+        WeightBalance { alpha: DEFAULT_ALPHA }
+    }
+}
+
+/**
+ * Weight-balanced tree, only insert and delete differ from the plain BST, the read API comes from BinarySearchTree through Deref
+ */
+pub type WeightBalancedTree<K, V = ()> = BalancedTree<K, V, WeightBalance>;
+
+impl<K: Ord + Debug, V> WeightBalancedTree<K, V> {
+    /**
+     * The rotations only restore the balance for an alpha above 2/11 and up to 1 - 1/sqrt(2), any other one is an AlphaError
+     */
+    pub fn with_alpha(alpha: f64) -> Result<Self, AlphaError> {
+        WeightBalancedTree::with_policy_and_alpha(DuplicatePolicy::default(), alpha)
+    }
+
+    pub fn with_policy_and_alpha(policy: DuplicatePolicy, alpha: f64) -> Result<Self, AlphaError> {
+        Ok(WeightBalancedTree::with_balance(policy, WeightBalance::with_alpha(alpha)?))
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.balance().alpha
+    }
+}
+
+impl WeightBalance {
+    pub fn with_alpha(alpha: f64) -> Result<Self, AlphaError> {
+        if !(alpha > 2.0 / 11.0 && alpha <= 1.0 - 1.0 / 2.0_f64.sqrt()) {
+            return Err(AlphaError { alpha, expected: "above 2/11 and up to 1 - 1/sqrt(2)" });
+        }
+
+        Ok(WeightBalance { alpha })
+    }
+
+    fn weight_of<K: Ord + Debug, V>(node: &Option<BstNodeLink<K, V>>) -> usize {
+        BstNode::size_of(node) + 1
+    }
+
+    fn rebalance_upward<K: Ord + Debug, V>(&self, tree: &mut BinarySearchTree<K, V>, mut x_node: Option<BstNodeLink<K, V>>) {
+        while let Some(current) = x_node {
+            let top = self.rebalance(tree, &current);
+            x_node = BstNode::upgrade_weak_to_strong(top.borrow().parent.clone());
+        }
+    }
+
+    //rotate if one side of x_node weighs less than alpha of it, return the node now at the top of the subtree
+    fn rebalance<K: Ord + Debug, V>(&self, tree: &mut BinarySearchTree<K, V>, x_node: &BstNodeLink<K, V>) -> BstNodeLink<K, V> {
+        let weight = (x_node.borrow().size + 1) as f64;
+        let left_weight = WeightBalance::weight_of(&x_node.borrow().left) as f64;
+        let right_weight = WeightBalance::weight_of(&x_node.borrow().right) as f64;
+
+        //the side that is too light, the heavy child on the other side moves up
+        let to_left = if left_weight < self.alpha * weight {
+            debug!("- The node {:?} is right heavy, its left subtree weighs {} out of {}", x_node.borrow().key, left_weight, weight);
+            true
+        } else if right_weight < self.alpha * weight {
+            debug!("- The node {:?} is left heavy, its right subtree weighs {} out of {}", x_node.borrow().key, right_weight, weight);
+            false
+        } else {
+            return x_node.clone();
+        };
+
+        let heavy = if to_left { x_node.borrow().right.clone() } else { x_node.borrow().left.clone() };
+        let heavy = heavy.expect("the heavy side has a child");
+
+        let inner = if to_left { heavy.borrow().left.clone() } else { heavy.borrow().right.clone() };
+        let inner_share = WeightBalance::weight_of(&inner) as f64 / (heavy.borrow().size + 1) as f64;
+
+        //a heavy inner grandchild would leave x_node out of balance after a single rotation
+        if inner_share > 1.0 / (2.0 - self.alpha) {
+            debug!("- The inner grandchild holds {:.2} of the child {:?}, rotate at the child first", inner_share, heavy.borrow().key);
+
+            tree.rotate(&heavy, !to_left);
+        }
+
+        tree.rotate(x_node, to_left)
+    }
+}
+
+impl<K: Ord + Debug, V> BalancePolicy<K, V> for WeightBalance {
+    type Meta = ();
+
+    const NAME: &'static str = "Weight-balanced tree";
+
+    //restore the balance from the parent of the new node up to the root
+    fn after_insert(&mut self, tree: &mut BinarySearchTree<K, V>, node: &BstNodeLink<K, V>) {
+        let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+        self.rebalance_upward(tree, parent);
+    }
+
+    //restore the balance from the lowest node that lost a node up to the root
+    fn after_delete(&mut self, tree: &mut BinarySearchTree<K, V>, lowest: Option<BstNodeLink<K, V>>) {
+        self.rebalance_upward(tree, lowest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::structure::bst::checks;
+
+    //check the weights below x_node against alpha
+    fn check_weights(x_node: &Option<BstNodeLink<u32>>, alpha: f64) {
+        let Some(x_node) = x_node else {
+            return;
+        };
+
+        let node = x_node.borrow();
+        let weight = (node.size + 1) as f64;
+
+        for side in [&node.left, &node.right] {
+            let side_weight = WeightBalance::weight_of(side) as f64;
+            assert!(side_weight >= alpha * weight, "a subtree of the node {:?} weighs {} out of {}", node.key, side_weight, weight);
+
+            check_weights(side, alpha);
+        }
+    }
+
+    fn check(tree: &WeightBalancedTree<u32>) {
+        check_weights(&tree.root(), tree.alpha());
+
+        //every step down keeps 1 - alpha of the weight at most
+        let bound = ((tree.len() + 1) as f64).ln() / (1.0 / (1.0 - tree.alpha())).ln();
+        assert!(tree.height() as f64 <= bound.floor() + 1.0);
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_weights() {
+        checks::random_inserts_and_deletes(&mut WeightBalancedTree::new(), 21, check);
+    }
+
+    #[test]
+    fn random_inserts_and_deletes_keep_the_weights_for_the_largest_alpha() {
+        checks::random_inserts_and_deletes(&mut WeightBalancedTree::with_alpha(1.0 - 1.0 / 2.0_f64.sqrt()).expect("the largest alpha is in range"), 210, check);
+    }
+
+    #[test]
+    fn an_alpha_out_of_range_is_refused() {
+        for alpha in [2.0 / 11.0, 0.3, 0.0, f64::NAN] {
+            assert!(WeightBalancedTree::<u32>::with_alpha(alpha).is_err());
+        }
+    }
+
+    #[test]
+    fn sorted_input_rotates_into_balance() {
+        let mut tree = WeightBalancedTree::new();
+        let keys: BTreeSet<u32> = (0..1000).collect();
+
+        for &key in &keys {
+            tree.insert(key);
+        }
+
+        checks::check_links(&tree, &keys);
+        check(&tree);
+        assert!(tree.rotations() > 0);
+    }
+}
@@ -1,5 +1,7 @@
 use crate::structure::tree::NodeLink;
 use crate::structure::bst::BstNodeLink;
+use crate::structure::persistent::PersistentLink;
+use std::fmt::Display;
 use std::fs::File;
 use std::io::Write;
 
@@ -30,17 +32,17 @@ fn node_traversal(node: &NodeLink) -> String{
     //we print the child nodes first
     let left_child = &node.borrow().left;
     //won't print anything if left child is None
-    new_info += &print_child(&node, left_child.as_ref());
+    new_info += &print_child(node, left_child.as_ref());
     let right_child = &node.borrow().right;
-    new_info += &print_child(&node, right_child.as_ref());
+    new_info += &print_child(node, right_child.as_ref());
     //now we need to traverse deeper
     if left_child.is_some(){
-        new_info += &node_traversal(&left_child.as_ref().unwrap());
+        new_info += &node_traversal(left_child.as_ref().unwrap());
     }
     if right_child.is_some(){
-        new_info += &node_traversal(&right_child.as_ref().unwrap());
+        new_info += &node_traversal(right_child.as_ref().unwrap());
     }
-    return new_info;
+    new_info
 }
 
 fn print_child(parent_node: &NodeLink, child_node: Option<&NodeLink>) -> String{
@@ -54,50 +56,117 @@ fn print_child(parent_node: &NodeLink, child_node: Option<&NodeLink>) -> String{
         new_info += &child.borrow().value.to_string();
         new_info += ";\n";
     }
-    return new_info;
+    new_info
 }
 
-pub fn generate_dotfile_bst(root: &BstNodeLink, output_path: &str){
+/**
+ * How the balancing data of a node shows up in the graph, e.g. the colour of a red-black node
+ * The plain BST draws its nodes as they are
+ */
+pub trait DotStyle {
+    //graphviz attributes of the node, None keeps the default look
+    fn dot_attributes(&self) -> Option<String> {
+        None
+    }
+
+    //text shown under the key, e.g. the priority of a treap node
+    fn dot_label(&self) -> Option<String> {
+        None
+    }
+}
+
+impl DotStyle for () {}
+
+pub fn generate_dotfile_bst<K: Display, V, M: DotStyle>(root: &BstNodeLink<K, V, M>, output_path: &str){
     let graph_name = " tree";
     let preamble = "graph".to_owned() + graph_name + "{\n";
     let epilogue = "}";
-    //pass either left child or right child but not the root
-    let graph_arrangement = node_traversal_bst(root);
+    //the root is n0, every other node gets the next number as it is reached
+    let mut next_id = 1;
+    let graph_arrangement = node_traversal_bst(root, 0, &mut next_id);
     //traverse the node as usual
     let final_text = preamble + &graph_arrangement + epilogue;
     let mut output = File::create(output_path).expect("Failed to create");
     let _ = output.write_all(final_text.as_bytes());}
 
-fn node_traversal_bst(node: &BstNodeLink) -> String{
+/**
+ * The nodes are named by number and the key goes into the label, so nodes sharing a key,
+ * e.g. under the Multiset policy or intervals starting together, stay apart in the graph
+ */
+fn node_traversal_bst<K: Display, V, M: DotStyle>(node: &BstNodeLink<K, V, M>, id: usize, next_id: &mut usize) -> String{
     let mut new_info: String = "".to_string();
-    //we print the child nodes first
-    let left_child = &node.borrow().left;
-    //won't print anything if left child is None
-    new_info += &print_child_bst(&node, left_child.as_ref());
-    let right_child = &node.borrow().right;
-    new_info += &print_child_bst(&node, right_child.as_ref());
-    //now we need to traverse deeper
-    if left_child.is_some(){
-        new_info += &node_traversal_bst(&left_child.as_ref().unwrap());
+    //every node is declared before its edges, so a tree of a single node still shows up
+    new_info += &print_node_bst(node, id);
+    let left_child = node.borrow().left.clone();
+    let right_child = node.borrow().right.clone();
+    //won't print anything for a child that is None
+    for child in left_child.iter().chain(right_child.iter()) {
+        let child_id = *next_id;
+        *next_id += 1;
+        new_info += &print_child_bst(id, child_id);
+        //now we need to traverse deeper
+        new_info += &node_traversal_bst(child, child_id, next_id);
     }
-    if right_child.is_some(){
-        new_info += &node_traversal_bst(&right_child.as_ref().unwrap());
+    new_info
+}
+
+fn print_node_bst<K: Display, V, M: DotStyle>(node: &BstNodeLink<K, V, M>, id: usize) -> String{
+    let key = escape_dot(&node.borrow().key.as_ref().unwrap().to_string());
+    let mut attributes = Vec::new();
+    match node.borrow().meta.dot_label() {
+        Some(label) => attributes.push(format!("label=\"{}\\n{}\"", key, escape_dot(&label))),
+        None => attributes.push(format!("label=\"{}\"", key)),
     }
-    return new_info;
+    if let Some(style) = node.borrow().meta.dot_attributes() {
+        attributes.push(style);
+    }
+    format!("\tn{} [{}];\n", id, attributes.join(", "))
 }
 
-fn print_child_bst(parent_node: &BstNodeLink, child_node: Option<&BstNodeLink>) -> String{
-    let mut new_info = "".to_string();
-    if let Some(child) = child_node {
-        //concat parent
-        new_info += "\t";
-        new_info += &parent_node.borrow().key.unwrap().to_string();
-        //next_info += node.borrow().parent.unwrap().value;
-        new_info += "--";
-        new_info += &child.borrow().key.unwrap().to_string();
-        new_info += ";\n";
+fn print_child_bst(parent_id: usize, child_id: usize) -> String{
+    format!("\tn{}--n{};\n", parent_id, child_id)
+}
+
+//the quotes and backslashes inside a quoted DOT string are escaped, so any key is a valid label
+fn escape_dot(text: &str) -> String{
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/**
+ * The same graph as generate_dotfile_bst for one version of a persistent BST, the nodes it shares with
+ * other versions show up like the rest since a version only sees its own root
+ */
+pub fn generate_dotfile_persistent<K: Display, V>(root: &PersistentLink<K, V>, output_path: &str){
+    let graph_name = " tree";
+    let preamble = "graph".to_owned() + graph_name + "{\n";
+    let epilogue = "}";
+    let graph_arrangement = node_traversal_persistent(root);
+    let final_text = preamble + &graph_arrangement + epilogue;
+    let mut output = File::create(output_path).expect("Failed to create");
+    let _ = output.write_all(final_text.as_bytes());}
+
+/**
+ * A version may be deep, e.g. after sorted inserts, so the walk keeps its own stack instead of recursing
+ * The nodes are numbered in preorder as they are popped, the same numbers the BST export gives
+ */
+fn node_traversal_persistent<K: Display, V>(node: &PersistentLink<K, V>) -> String{
+    let mut new_info: String = "".to_string();
+    let mut next_id = 0;
+    //each entry is a node still to print along with the number of its parent
+    let mut stack: Vec<(&PersistentLink<K, V>, Option<usize>)> = vec![(node, None)];
+    while let Some((current, parent_id)) = stack.pop() {
+        let id = next_id;
+        next_id += 1;
+        new_info += &format!("\tn{} [label=\"{}\"];\n", id, escape_dot(&current.key().to_string()));
+        if let Some(parent_id) = parent_id {
+            new_info += &print_child_bst(parent_id, id);
+        }
+        //no RefCell to borrow, the children are read straight off the node, the right one goes in first so the left one comes out first
+        for child in current.right().into_iter().chain(current.left()) {
+            stack.push((child, Some(id)));
+        }
     }
-    return new_info;
+    new_info
 }
 
 /*